@@ -0,0 +1,30 @@
+#[macro_use]
+extern crate criterion;
+extern crate game;
+
+use std::fs;
+use std::path::Path;
+
+use criterion::Criterion;
+
+use game::utils::tmx::{parse_tmx, parse_tmx_document, TilesetCache};
+
+fn parse_maps(c: &mut Criterion) {
+    for name in &["town", "topworld", "castle_of_doom"] {
+        let file_name = Path::new(env!("CARGO_MANIFEST_DIR")).join(format!("../../assets/maps/{}.tmx", name));
+        let buffer = fs::read(&file_name).unwrap();
+        let base_dir = file_name.parent().unwrap().to_path_buf();
+
+        let mut group = c.benchmark_group(*name);
+        group.bench_function("stream", |b| {
+            b.iter(|| parse_tmx(&buffer[..], &base_dir, &mut TilesetCache::new()).unwrap())
+        });
+        group.bench_function("document", |b| {
+            b.iter(|| parse_tmx_document(&buffer[..], &base_dir, &mut TilesetCache::new()).unwrap())
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, parse_maps);
+criterion_main!(benches);
@@ -1,7 +1,9 @@
 
 extern crate noisy_float;
+#[cfg(test)]
+extern crate proptest;
 
-mod utils;
+pub mod utils;
 pub mod world;
 
 #[cfg(test)]
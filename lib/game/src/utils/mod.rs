@@ -1,10 +1,28 @@
 
 pub mod tmx;
 
+#[cfg(test)]
+use std::path::{Path, PathBuf};
+
 pub fn epsilon(num1: f32, num2: f32, epsilon: f32) -> bool {
     return (num1 - num2).abs() <= epsilon
 }
 
+/// A file of the game's `assets` directory, independent of where the tests
+/// are run from.
+#[cfg(test)]
+pub fn asset_path(path: &str) -> PathBuf {
+    tmx::normalize(&Path::new(env!("CARGO_MANIFEST_DIR")).join("../../assets").join(path))
+}
+
+/// The maps the game ships with.
+#[cfg(test)]
+pub fn shipped_maps() -> Vec<PathBuf> {
+    ["town", "topworld", "castle_of_doom"].iter()
+        .map(|name| asset_path(&format!("maps/{}.tmx", name)))
+        .collect()
+}
+
 #[cfg(test)]
 mod test {
 
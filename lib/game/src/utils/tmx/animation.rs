@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use super::Node;
+use super::TmxContent;
+use super::error::TmxError;
+use super::tileset::TmxTileset;
+
+/// One `<frame>` of a tile animation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame {
+    pub tileid: usize,
+    pub duration: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Animation {
+    pub frames: Vec<Frame>,
+}
+
+impl Animation {
+
+    pub fn from_node(node: &Node) -> Result<Animation, TmxError> {
+        let mut frames = Vec::new();
+
+        for node in node.children.iter().filter(|node| node.name == "frame") {
+            let tileid = node.attributes.get("tileid").ok_or_else(|| node.missing_attribute("tileid"))?;
+            let duration = node.attributes.get("duration").ok_or_else(|| node.missing_attribute("duration"))?;
+            frames.push(Frame {
+                tileid: node.parse::<usize>("tileid", tileid)?,
+                duration: Duration::from_millis(node.parse::<u64>("duration", duration)?),
+            });
+        }
+
+        Ok(Animation { frames })
+    }
+
+    pub fn total_duration(&self) -> Duration {
+        self.frames.iter().map(|frame| frame.duration).sum()
+    }
+
+    /// The frame shown `elapsed` after the animation started, looping forever.
+    pub fn frame_at(&self, elapsed: Duration) -> Option<&Frame> {
+        let total = self.total_duration().as_millis();
+        if total == 0 {
+            return self.frames.first();
+        }
+
+        let mut time = elapsed.as_millis() % total;
+        for frame in self.frames.iter() {
+            let duration = frame.duration.as_millis();
+            if time < duration {
+                return Some(frame);
+            }
+            time -= duration;
+        }
+
+        self.frames.last()
+    }
+
+}
+
+/// Looks up the frame to draw for animated tiles such as water, torches or
+/// the `Light` tileset. All animations share one clock.
+#[derive(Debug, Default)]
+pub struct TileAnimator {
+    /// The animations keyed by gid, with the `firstgid` their frames count from.
+    animations: HashMap<usize, (usize, Animation)>,
+}
+
+impl TileAnimator {
+
+    pub fn new() -> TileAnimator {
+        TileAnimator::default()
+    }
+
+    pub fn from_content(content: &TmxContent) -> TileAnimator {
+        let mut animator = TileAnimator::new();
+        for tileset in content.tilesets() {
+            animator.add_tileset(tileset);
+        }
+        animator
+    }
+
+    pub fn add_tileset(&mut self, tileset: &TmxTileset) {
+        for tile in tileset.tiles.values() {
+            if let Some(ref animation) = tile.animation {
+                self.animations.insert(tileset.firstgid + tile.id, (tileset.firstgid, animation.clone()));
+            }
+        }
+    }
+
+    pub fn is_animated(&self, gid: usize) -> bool {
+        self.animations.contains_key(&gid)
+    }
+
+    /// The gid to draw in place of `gid` after `elapsed`; `gid` itself if it is not animated.
+    pub fn current_gid(&self, gid: usize, elapsed: Duration) -> usize {
+        self.animations.get(&gid)
+            .and_then(|&(firstgid, ref animation)| animation.frame_at(elapsed).map(|frame| firstgid + frame.tileid))
+            .unwrap_or(gid)
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+
+    use ::noisy_float::prelude::*;
+    use super::*;
+    use ::world::Area;
+    use super::super::{Object, ObjectShape};
+    use super::super::fixtures::{map, parse_tmx};
+
+    use std::time::Duration;
+
+    #[test]
+    fn animate_tiles() {
+        let xml = map(r#" <tileset firstgid="177" name="Light" tilewidth="16" tileheight="16" tilecount="8" columns="8">
+  <image source="../sprites/items/Light.png" width="128" height="16"/>
+  <tile id="0">
+   <animation>
+    <frame tileid="0" duration="100"/>
+    <frame tileid="1" duration="200"/>
+    <frame tileid="2" duration="100"/>
+   </animation>
+  </tile>
+  <tile id="4">
+   <objectgroup draworder="index">
+    <object id="1" x="2" y="4" width="12" height="10"/>
+    <object id="2" x="0" y="0">
+     <ellipse/>
+    </object>
+   </objectgroup>
+  </tile>
+ </tileset>"#);
+        let tmx_content = parse_tmx(xml.as_bytes()).unwrap();
+        let light = tmx_content.get_tileset("Light").unwrap();
+
+        let animation = light.tile(177).unwrap().animation.as_ref().unwrap();
+        assert_eq!(animation.frames.len(), 3);
+        assert_eq!(animation.frames[1].tileid, 1);
+        assert_eq!(animation.total_duration(), Duration::from_millis(400));
+
+        let animator = TileAnimator::from_content(&tmx_content);
+        assert!(animator.is_animated(177));
+        assert_eq!(animator.current_gid(177, Duration::from_millis(0)), 177);
+        assert_eq!(animator.current_gid(177, Duration::from_millis(99)), 177);
+        assert_eq!(animator.current_gid(177, Duration::from_millis(100)), 178);
+        assert_eq!(animator.current_gid(177, Duration::from_millis(299)), 178);
+        assert_eq!(animator.current_gid(177, Duration::from_millis(300)), 179);
+        assert_eq!(animator.current_gid(177, Duration::from_millis(450)), 177);
+        assert_eq!(animator.current_gid(180, Duration::from_millis(450)), 180);
+
+        let torch = light.tile(181).unwrap();
+        assert!(torch.animation.is_none());
+        let mut shapes: Vec<&Object> = torch.collision_shapes().collect();
+        shapes.sort_by_key(|object| object.id);
+        assert_eq!(shapes.len(), 2);
+        assert_eq!(shapes[0].area, Area::new(r32(2.0), r32(4.0), r32(12.0), r32(10.0)));
+        assert_eq!(shapes[1].shape, ObjectShape::Ellipse);
+    }
+
+}
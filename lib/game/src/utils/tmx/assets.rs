@@ -0,0 +1,162 @@
+use std::path::{Path, PathBuf};
+
+use super::{TmxContent, TmxEntry};
+use super::error::TmxError;
+use super::imagelayer::TmxImageLayer;
+use super::resolver::{Resolver, normalize};
+use super::tileset::TmxTileset;
+
+/// What a map refers to a file for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AssetKind {
+    /// The `.tsx` or `.tsj` of an external tileset.
+    Tileset,
+    TilesetImage,
+    ImageLayer,
+}
+
+/// A file a map refers to, with the `source` as written in the map or
+/// tileset and the path it resolves to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetPath {
+    pub kind: AssetKind,
+    pub name: String,
+    pub reference: String,
+    pub path: PathBuf,
+}
+
+impl TmxContent {
+
+    /// The `.tsx` or `.tsj` file of an external tileset.
+    pub fn tileset_path(&self, tileset: &TmxTileset) -> Option<PathBuf> {
+        tileset.source.as_ref().map(|source| normalize(&self.base_dir().join(source)))
+    }
+
+    /// The image a tileset is cut from. It is relative to the tileset's own
+    /// file for external tilesets and to the map otherwise.
+    pub fn tileset_image_path(&self, tileset: &TmxTileset) -> Option<PathBuf> {
+        let image = tileset.image.as_ref()?;
+        let dir = match self.tileset_path(tileset) {
+            Some(path) => path.parent().map(Path::to_path_buf).unwrap_or_default(),
+            None => self.base_dir().to_path_buf(),
+        };
+        Some(normalize(&dir.join(&image.source)))
+    }
+
+    pub fn image_layer_path(&self, image_layer: &TmxImageLayer) -> Option<PathBuf> {
+        image_layer.image.as_ref().map(|image| normalize(&self.base_dir().join(&image.source)))
+    }
+
+    /// Every file the map refers to, in document order.
+    pub fn asset_paths(&self) -> Vec<AssetPath> {
+        let mut assets = Vec::new();
+
+        for visit in self.iter() {
+            match *visit.entry {
+                TmxEntry::Tileset(ref tileset) => {
+                    if let (Some(source), Some(path)) = (tileset.source.as_ref(), self.tileset_path(tileset)) {
+                        assets.push(asset(AssetKind::Tileset, &tileset.name, source, path));
+                    }
+                    if let (Some(image), Some(path)) = (tileset.image.as_ref(), self.tileset_image_path(tileset)) {
+                        assets.push(asset(AssetKind::TilesetImage, &tileset.name, &image.source, path));
+                    }
+                }
+                TmxEntry::ImageLayer(ref image_layer) => {
+                    if let (Some(image), Some(path)) = (image_layer.image.as_ref(), self.image_layer_path(image_layer)) {
+                        assets.push(asset(AssetKind::ImageLayer, &image_layer.name, &image.source, path));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        assets
+    }
+
+    /// The files the map refers to that `resolver` does not have.
+    pub fn missing_assets(&self, resolver: &dyn Resolver) -> Vec<AssetPath> {
+        self.asset_paths().into_iter().filter(|asset| !resolver.exists(&asset.path)).collect()
+    }
+
+    /// Fails with `MissingAsset` for the first file the map refers to that
+    /// `resolver` does not have.
+    pub fn check_assets(&self, resolver: &dyn Resolver) -> Result<(), TmxError> {
+        match self.missing_assets(resolver).into_iter().next() {
+            Some(asset) => Err(TmxError::MissingAsset {
+                name: asset.name,
+                reference: asset.reference,
+                path: asset.path,
+            }),
+            None => Ok(()),
+        }
+    }
+
+}
+
+fn asset(kind: AssetKind, name: &str, reference: &str, path: PathBuf) -> AssetPath {
+    AssetPath {
+        kind,
+        name: name.to_string(),
+        reference: reference.to_string(),
+        path,
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use ::utils::{asset_path, shipped_maps};
+    use super::super::resolver::{FileResolver, MemoryResolver};
+    use super::super::{TilesetCache, load_map, load_map_with_cache};
+
+    #[test]
+    fn resolve_paths_relative_to_the_map() {
+        let topworld = load_map(asset_path("maps/topworld.tmx")).unwrap();
+        assert_eq!(topworld.base_dir(), asset_path("maps"));
+
+        let wall = topworld.get_tileset("Wall").unwrap();
+        assert_eq!(wall.image_file_name, "../sprites/objects/Wall.png");
+        assert_eq!(topworld.tileset_path(wall), None);
+        assert_eq!(topworld.tileset_image_path(wall), Some(asset_path("sprites/objects/Wall.png")));
+
+        let lightmap = topworld.image_layers().next().unwrap();
+        assert_eq!(topworld.image_layer_path(lightmap), Some(asset_path("maps/topworld_lightmap_dawn.png")));
+
+        for file_name in shipped_maps() {
+            load_map(file_name).unwrap().check_assets(&FileResolver).unwrap();
+        }
+    }
+
+    #[test]
+    fn resolve_external_tileset_images_relative_to_the_tileset() {
+        let mut files = MemoryResolver::new();
+        files.insert("maps/tilesets/Wall.tsx", r#"<tileset name="Wall" tilewidth="16" tileheight="16" tilecount="1" columns="1">
+ <image source="../../sprites/Wall.png" width="16" height="16"/>
+</tileset>"#);
+        files.insert("maps/town.tmx", r#"<map version="1.0" orientation="orthogonal" width="1" height="1" tilewidth="16" tileheight="16">
+ <tileset firstgid="1" source="tilesets/Wall.tsx"/>
+ <imagelayer name="Light"><image source="./light.png"/></imagelayer>
+</map>"#);
+        files.insert("maps/light.png", vec![0u8]);
+
+        let town = load_map_with_cache("maps/town.tmx", &mut TilesetCache::with_resolver(files.clone())).unwrap();
+        let assets: Vec<(AssetKind, PathBuf)> = town.asset_paths().into_iter().map(|asset| (asset.kind, asset.path)).collect();
+        assert_eq!(assets, vec![
+            (AssetKind::Tileset, PathBuf::from("maps/tilesets/Wall.tsx")),
+            (AssetKind::TilesetImage, PathBuf::from("sprites/Wall.png")),
+            (AssetKind::ImageLayer, PathBuf::from("maps/light.png")),
+        ]);
+
+        match town.check_assets(&files) {
+            Err(TmxError::MissingAsset { ref name, ref reference, ref path }) => {
+                assert_eq!((name.as_str(), reference.as_str(), path.as_path()), ("Wall", "../../sprites/Wall.png", Path::new("sprites/Wall.png")));
+            }
+            other => panic!("expected a missing image, got {:?}", other),
+        }
+
+        files.insert("sprites/Wall.png", vec![0u8]);
+        town.check_assets(&files).unwrap();
+    }
+
+}
@@ -0,0 +1,124 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use super::TmxContent;
+use super::error::TmxError;
+use super::tsx::TilesetCache;
+
+/// A map being loaded on a worker thread, started by `load_map_in_background`.
+/// Poll it once per frame; the result is handed out exactly once.
+#[derive(Debug)]
+pub struct MapLoad {
+    path: PathBuf,
+    receiver: Option<Receiver<Result<TmxContent, TmxError>>>,
+}
+
+impl MapLoad {
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// `true` once `poll` or `wait` has returned the result.
+    pub fn is_done(&self) -> bool {
+        self.receiver.is_none()
+    }
+
+    /// The loaded map or the error, if the worker has finished; `None` while it
+    /// is still loading and on every call after the result was taken.
+    pub fn poll(&mut self) -> Option<Result<TmxContent, TmxError>> {
+        let result = match self.receiver.as_ref()?.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => Err(self.aborted()),
+        };
+        self.receiver = None;
+        Some(result)
+    }
+
+    /// Blocks until the worker has finished. Fails with `LoadAborted` if the
+    /// worker panicked or `poll` has returned the result already.
+    pub fn wait(mut self) -> Result<TmxContent, TmxError> {
+        match self.receiver.take() {
+            Some(receiver) => receiver.recv().unwrap_or_else(|_| Err(self.aborted())),
+            None => Err(self.aborted()),
+        }
+    }
+
+    fn aborted(&self) -> TmxError {
+        TmxError::LoadAborted { path: self.path.display().to_string() }
+    }
+
+}
+
+/// Starts loading the map at `path` on a new thread, see `load_map`.
+pub fn load_map_in_background<P: AsRef<Path>>(path: P) -> MapLoad {
+    load_map_in_background_with_cache(path, TilesetCache::new())
+}
+
+/// Like `load_map_in_background`, with the worker using `cache`. Pass a clone
+/// of the cache the game loads with to reuse the tilesets it already parsed;
+/// the clone shares them instead of copying.
+pub fn load_map_in_background_with_cache<P: AsRef<Path>>(path: P, mut cache: TilesetCache) -> MapLoad {
+    let path = path.as_ref().to_path_buf();
+    let (sender, receiver) = mpsc::channel();
+
+    let worker_path = path.clone();
+    thread::spawn(move || {
+        // the receiver may be gone already when the load was abandoned
+        let _ = sender.send(super::load_map_with_cache(&worker_path, &mut cache));
+    });
+
+    MapLoad {
+        path,
+        receiver: Some(receiver),
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use ::utils::asset_path;
+    use super::super::load_map;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+    fn assert_send<T: Send>() {}
+
+    #[test]
+    fn model_is_send_and_sync() {
+        assert_send_sync::<TmxContent>();
+        assert_send_sync::<TmxError>();
+        assert_send_sync::<TilesetCache>();
+        assert_send::<MapLoad>();
+    }
+
+    #[test]
+    fn load_town_in_background() {
+        let town = asset_path("maps/town.tmx");
+        let expected = load_map(&town).unwrap();
+
+        let mut load = load_map_in_background(&town);
+        let content = loop {
+            if let Some(result) = load.poll() {
+                break result.unwrap();
+            }
+            thread::yield_now();
+        };
+        assert!(load.is_done());
+        assert!(load.poll().is_none());
+        assert_eq!(content, expected);
+        assert_eq!(load.wait().unwrap_err().to_string(),
+            format!("map '{}' was not loaded: its worker stopped or the result was taken already", town.display()));
+    }
+
+    #[test]
+    fn background_errors_are_returned() {
+        match load_map_in_background(asset_path("maps/missing.tmx")).wait() {
+            Err(TmxError::Io(_)) => {}
+            other => panic!("expected an io error, got {:?}", other),
+        }
+    }
+
+}
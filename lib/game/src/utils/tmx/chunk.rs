@@ -0,0 +1,171 @@
+use super::Node;
+use super::data::decode_chunk_data;
+use super::error::TmxError;
+use super::layer::{Grid, TileRef};
+
+/// A `<chunk>` of an infinite layer; `x`/`y` are in tiles and may be negative.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    pub x: i32,
+    pub y: i32,
+    pub width: usize,
+    pub height: usize,
+    pub tiles: Vec<TileRef>,
+}
+
+impl Chunk {
+
+    pub fn from_node(data: &Node, node: &Node) -> Result<Chunk, TmxError> {
+        let attribute = |name: &str| node.attributes.get(name).ok_or_else(|| node.missing_attribute(name));
+
+        let x = node.parse::<i32>("x", attribute("x")?)?;
+        let y = node.parse::<i32>("y", attribute("y")?)?;
+        let width = node.parse::<usize>("width", attribute("width")?)?;
+        let height = node.parse::<usize>("height", attribute("height")?)?;
+        let cells = width.checked_mul(height).ok_or_else(|| node.invalid_value("width", &width.to_string()))?;
+
+        Ok(Chunk {
+            x,
+            y,
+            width,
+            height,
+            tiles: decode_chunk_data(data, node, cells)?.into_iter().map(TileRef::from).collect(),
+        })
+    }
+
+    fn get(&self, x: i32, y: i32) -> Option<TileRef> {
+        if x < self.x || y < self.y {
+            return None;
+        }
+        let col = (x - self.x) as usize;
+        let row = (y - self.y) as usize;
+        if col < self.width && row < self.height {
+            Some(self.tiles[row * self.width + col])
+        } else {
+            None
+        }
+    }
+
+}
+
+/// The sparse tile data of an infinite layer. Chunks keep their own size
+/// and offset, as Tiled does not require them to line up.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ChunkedGrid {
+    chunks: Vec<Chunk>,
+}
+
+impl ChunkedGrid {
+
+    pub fn new(chunks: Vec<Chunk>) -> ChunkedGrid {
+        ChunkedGrid { chunks }
+    }
+
+    pub fn from_node(data: &Node) -> Result<ChunkedGrid, TmxError> {
+        let chunks = data.children.iter()
+            .filter(|node| node.name == "chunk")
+            .map(|node| Chunk::from_node(data, node))
+            .collect::<Result<Vec<Chunk>, TmxError>>()?;
+
+        Ok(ChunkedGrid { chunks })
+    }
+
+    pub fn chunks(&self) -> impl Iterator<Item = &Chunk> {
+        self.chunks.iter()
+    }
+
+    /// The tile at `x`/`y`, or `None` outside of every chunk.
+    pub fn get(&self, x: i32, y: i32) -> Option<TileRef> {
+        self.chunks.iter().filter_map(|chunk| chunk.get(x, y)).next()
+    }
+
+    /// The smallest rectangle covering all chunks as `(x, y, width, height)` in tiles.
+    pub fn bounds(&self) -> (i32, i32, usize, usize) {
+        let left = self.chunks.iter().map(|chunk| chunk.x).min().unwrap_or(0);
+        let top = self.chunks.iter().map(|chunk| chunk.y).min().unwrap_or(0);
+        let right = self.chunks.iter().map(|chunk| chunk.x + chunk.width as i32).max().unwrap_or(0);
+        let bottom = self.chunks.iter().map(|chunk| chunk.y + chunk.height as i32).max().unwrap_or(0);
+        (left, top, (right - left) as usize, (bottom - top) as usize)
+    }
+
+    /// Copies the chunks into a bounded grid whose top left cell is at
+    /// `bounds().0`/`bounds().1`; cells not covered by a chunk are empty.
+    pub fn flatten(&self) -> Grid {
+        let (left, top, width, height) = self.bounds();
+        let mut grid = Grid::new(width, height);
+
+        for chunk in self.chunks.iter() {
+            let col = (chunk.x - left) as usize;
+            for row in 0 .. chunk.height {
+                let start = row * chunk.width;
+                grid[(chunk.y - top) as usize + row][col .. col + chunk.width]
+                    .copy_from_slice(&chunk.tiles[start .. start + chunk.width]);
+            }
+        }
+
+        grid
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use super::super::fixtures::parse_tmx;
+
+    #[test]
+    fn read_infinite_map_chunks() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" renderorder="right-down" width="30" height="20" tilewidth="16" tileheight="16" infinite="1" nextlayerid="2" nextobjectid="1">
+ <layer id="1" name="Ground_Layer" width="30" height="20">
+  <data encoding="csv">
+   <chunk x="-2" y="0" width="2" height="2">
+1,2,
+3,4
+</chunk>
+   <chunk x="2" y="-2" width="2" height="2">
+5,0,
+0,2147483654
+</chunk>
+  </data>
+ </layer>
+</map>"#;
+        let tmx_content = parse_tmx(xml.as_bytes()).unwrap();
+        assert!(tmx_content.map.infinite);
+
+        let layer = tmx_content.get_layer("Ground_Layer").unwrap();
+        let chunks = layer.chunks.as_ref().unwrap();
+        assert_eq!(chunks.chunks().count(), 2);
+        assert_eq!((layer.width, layer.height), (30, 20));
+
+        assert_eq!(layer.get(-2, 0).unwrap().gid, 1);
+        assert_eq!(layer.get(-1, 1).unwrap().gid, 4);
+        assert_eq!(layer.get(2, -2).unwrap().gid, 5);
+        assert_eq!(layer.get(3, -1), Some(TileRef { gid: 6, flip_h: true, ..TileRef::default() }));
+        assert_eq!(layer.get(0, 0), None);
+        assert_eq!(layer.get(-3, 0), None);
+
+        assert_eq!(chunks.bounds(), (-2, -2, 6, 4));
+        let grid = chunks.flatten();
+        assert_eq!((grid.width(), grid.height()), (6, 4));
+        assert_eq!(grid[2][0].gid, 1);
+        assert_eq!(grid[3][1].gid, 4);
+        assert_eq!(grid[0][4].gid, 5);
+        assert_eq!(grid[1][5].gid, 6);
+        assert!(grid[0][0].is_empty());
+
+        let unaligned = xml.replace(r#"<chunk x="2" y="-2" width="2" height="2">
+5,0,
+0,2147483654"#, r#"<chunk x="1" y="-3" width="3" height="1">
+7,8,9"#);
+        let tmx_content = parse_tmx(unaligned.as_bytes()).unwrap();
+        let layer = tmx_content.get_layer("Ground_Layer").unwrap();
+        assert_eq!(layer.get(1, -3).unwrap().gid, 7);
+        assert_eq!(layer.get(3, -3).unwrap().gid, 9);
+        assert_eq!(layer.get(-1, 0).unwrap().gid, 2);
+        assert_eq!(layer.get(4, -3), None);
+        assert_eq!(layer.chunks.as_ref().unwrap().bounds(), (-2, -3, 6, 5));
+    }
+
+}
@@ -0,0 +1,50 @@
+use std::fmt;
+
+use super::Node;
+use super::error::TmxError;
+
+/// A color as written by Tiled, either `#RRGGBB` or `#AARRGGBB`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Color {
+
+    pub fn new(r: u8, g: u8, b: u8, a: u8) -> Color {
+        Color { r, g, b, a }
+    }
+
+    pub fn parse(value: &str) -> Option<Color> {
+        let hex = value.trim().trim_start_matches('#');
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let byte = |idx: usize| u8::from_str_radix(&hex[idx .. idx + 2], 16).ok();
+        match hex.len() {
+            6 => Some(Color::new(byte(0)?, byte(2)?, byte(4)?, 255)),
+            8 => Some(Color::new(byte(2)?, byte(4)?, byte(6)?, byte(0)?)),
+            _ => None,
+        }
+    }
+
+    pub fn from_node(node: &Node, attribute: &str, value: &str) -> Result<Color, TmxError> {
+        Color::parse(value).ok_or_else(|| node.invalid_value(attribute, value))
+    }
+
+    /// Component-wise product, the way Tiled combines nested tint colors.
+    pub fn multiply(&self, other: &Color) -> Color {
+        let mul = |a: u8, b: u8| ((a as u16 * b as u16 + 127) / 255) as u8;
+        Color::new(mul(self.r, other.r), mul(self.g, other.g), mul(self.b, other.b), mul(self.a, other.a))
+    }
+
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}{:02x}", self.a, self.r, self.g, self.b)
+    }
+}
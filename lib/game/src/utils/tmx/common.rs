@@ -0,0 +1,134 @@
+use ::noisy_float::prelude::*;
+
+use super::Node;
+use super::color::Color;
+use super::error::TmxError;
+
+/// The attributes Tiled writes on every kind of layer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayerCommon {
+    /// Unique layer id, written by Tiled 1.2 and later.
+    pub id: Option<usize>,
+    pub visible: bool,
+    pub opacity: R32,
+    pub offsetx: R32,
+    pub offsety: R32,
+    pub tint_color: Option<Color>,
+    pub parallaxx: R32,
+    pub parallaxy: R32,
+}
+
+impl Default for LayerCommon {
+    fn default() -> LayerCommon {
+        LayerCommon {
+            id: None,
+            visible: true,
+            opacity: r32(1.0),
+            offsetx: r32(0.0),
+            offsety: r32(0.0),
+            tint_color: None,
+            parallaxx: r32(1.0),
+            parallaxy: r32(1.0),
+        }
+    }
+}
+
+impl LayerCommon {
+
+    pub fn from_node(node: &Node) -> Result<LayerCommon, TmxError> {
+
+        let mut common = LayerCommon::default();
+
+        for (key, value) in &node.attributes {
+            match key.as_ref() {
+                "id" => {
+                    common.id = Some(node.parse::<usize>(key, value)?);
+                }
+                "visible" => {
+                    common.visible = node.parse::<u8>(key, value)? != 0;
+                }
+                "opacity" => {
+                    common.opacity = node.parse_r32(key, value)?;
+                }
+                "offsetx" => {
+                    common.offsetx = node.parse_r32(key, value)?;
+                }
+                "offsety" => {
+                    common.offsety = node.parse_r32(key, value)?;
+                }
+                "tintcolor" => {
+                    common.tint_color = Some(Color::from_node(node, key, value)?);
+                }
+                "parallaxx" => {
+                    common.parallaxx = node.parse_r32(key, value)?;
+                }
+                "parallaxy" => {
+                    common.parallaxy = node.parse_r32(key, value)?;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(common)
+    }
+
+    /// The effective attributes of a layer nested in a group with the
+    /// (already effective) attributes `parent`: offsets add up, opacity,
+    /// parallax and tint multiply and a hidden group hides its children.
+    pub fn inherit(&self, parent: &LayerCommon) -> LayerCommon {
+        LayerCommon {
+            id: self.id,
+            visible: self.visible && parent.visible,
+            opacity: self.opacity * parent.opacity,
+            offsetx: self.offsetx + parent.offsetx,
+            offsety: self.offsety + parent.offsety,
+            tint_color: match (self.tint_color, parent.tint_color) {
+                (Some(tint), Some(inherited)) => Some(tint.multiply(&inherited)),
+                (tint, inherited) => tint.or(inherited),
+            },
+            parallaxx: self.parallaxx * parent.parallaxx,
+            parallaxy: self.parallaxy * parent.parallaxy,
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+
+    use ::noisy_float::prelude::*;
+    use super::*;
+    use ::utils::asset_path;
+    use super::super::TmxContent;
+    use super::super::fixtures::{map, parse_tmx};
+
+    #[test]
+    fn skip_hidden_layers() {
+        let town = TmxContent::load(asset_path("maps/town.tmx")).unwrap();
+        let visible: Vec<&str> = town.visible_layers().map(|entry| entry.name().as_ref()).collect();
+        assert_eq!(visible, vec!["Background_Layer", "Ground_Layer", "Decoration_Layer", "PARTICLE_EFFECT_SPAWN_LAYER"]);
+
+        let collision = town.get_object_group("MAP_COLLISION_LAYER").unwrap();
+        assert!(!collision.common.visible);
+    }
+
+    #[test]
+    fn read_layer_common_attributes() {
+        let xml = map(r##" <layer name="Ground" width="1" height="1" opacity="0.5" offsetx="4" offsety="-2.5" tintcolor="#ff8000" parallaxx="0.5" parallaxy="2">
+  <data encoding="csv">1</data>
+ </layer>
+ <objectgroup name="Objects" visible="0"/>"##);
+        let tmx_content = parse_tmx(xml.as_bytes()).unwrap();
+
+        let common = &tmx_content.get_layer("Ground").unwrap().common;
+        assert!(common.visible);
+        assert_eq!(common.opacity, r32(0.5));
+        assert_eq!((common.offsetx, common.offsety), (r32(4.0), r32(-2.5)));
+        assert_eq!(common.tint_color, Some(Color::new(255, 128, 0, 255)));
+        assert_eq!((common.parallaxx, common.parallaxy), (r32(0.5), r32(2.0)));
+
+        let common = &tmx_content.get_object_group("Objects").unwrap().common;
+        assert_eq!(*common, LayerCommon { visible: false, ..LayerCommon::default() });
+    }
+
+}
@@ -0,0 +1,235 @@
+extern crate base64;
+extern crate flate2;
+extern crate zstd;
+
+use std::io::{Read};
+
+use self::base64::Engine;
+use self::base64::engine::general_purpose::STANDARD;
+use self::flate2::read::{GzDecoder, ZlibDecoder};
+
+use super::Node;
+use super::error::TmxError;
+
+/// Decodes the raw tile gids stored in a `<data>` element into row-major order.
+///
+/// Supports every encoding Tiled writes: csv, base64 (uncompressed, zlib,
+/// gzip or zstd) and the legacy form with one `<tile gid>` child per cell.
+pub fn decode_tile_data(data: &Node, cells: usize) -> Result<Vec<u32>, TmxError> {
+    decode(data, data, cells)
+}
+
+/// Decodes one `<chunk>` of an infinite map, using the encoding of its parent `<data>`.
+pub fn decode_chunk_data(data: &Node, chunk: &Node, cells: usize) -> Result<Vec<u32>, TmxError> {
+    decode(data, chunk, cells)
+}
+
+fn decode(data: &Node, content: &Node, cells: usize) -> Result<Vec<u32>, TmxError> {
+
+    let encoding = data.attributes.get("encoding").map(String::as_str);
+    let compression = data.attributes.get("compression").map(String::as_str);
+
+    let tiles = match (encoding, compression) {
+        (Some("csv"), None) => decode_csv(content)?,
+        (Some("base64"), compression) => decode_base64(content, compression)?,
+        (None, None) => decode_xml(content)?,
+        (Some(encoding), _) => return Err(unsupported(data, "encoding", encoding)),
+        (None, Some(compression)) => return Err(unsupported(data, "compression", compression)),
+    };
+
+    if tiles.len() != cells {
+        return Err(TmxError::CellCount {
+            element: content.name.clone(),
+            expected: cells,
+            found: tiles.len(),
+            position: content.position,
+        });
+    }
+
+    Ok(tiles)
+}
+
+fn decode_csv(data: &Node) -> Result<Vec<u32>, TmxError> {
+
+    let content = data.content.trim().trim_end_matches(',');
+
+    if content.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    content.split(',')
+        .map(str::trim)
+        .enumerate()
+        .map(|(idx, str)| {
+            str.parse::<u32>().map_err(|_| TmxError::MalformedCsv {
+                element: data.name.clone(),
+                reason: format!("cell {} is not a tile id: '{}'", idx, str),
+                position: data.position,
+            })
+        })
+        .collect()
+}
+
+fn decode_base64(data: &Node, compression: Option<&str>) -> Result<Vec<u32>, TmxError> {
+
+    let content: String = data.content.chars().filter(|c| !c.is_whitespace()).collect();
+    let bytes = STANDARD.decode(content.as_bytes())
+        .map_err(|err| malformed(data, format!("invalid base64: {}", err)))?;
+
+    let bytes = match compression {
+        None => bytes,
+        Some("zlib") => inflate(data, ZlibDecoder::new(&bytes[..]), "zlib")?,
+        Some("gzip") => inflate(data, GzDecoder::new(&bytes[..]), "gzip")?,
+        Some("zstd") => zstd::stream::decode_all(&bytes[..])
+            .map_err(|err| malformed(data, format!("invalid zstd stream: {}", err)))?,
+        Some(compression) => return Err(unsupported(data, "compression", compression)),
+    };
+
+    if bytes.len() % 4 != 0 {
+        return Err(malformed(data, format!("{} bytes is not a whole number of tiles", bytes.len())));
+    }
+
+    Ok(bytes.chunks(4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect())
+}
+
+fn inflate<R: Read>(data: &Node, mut decoder: R, compression: &str) -> Result<Vec<u8>, TmxError> {
+    let mut bytes = Vec::new();
+    decoder.read_to_end(&mut bytes)
+        .map_err(|err| malformed(data, format!("invalid {} stream: {}", compression, err)))?;
+    Ok(bytes)
+}
+
+fn decode_xml(data: &Node) -> Result<Vec<u32>, TmxError> {
+    data.children.iter()
+        .filter(|node| node.name == "tile")
+        .map(|node| match node.attributes.get("gid") {
+            Some(gid) => node.parse::<u32>("gid", gid),
+            None => Ok(0),
+        })
+        .collect()
+}
+
+fn malformed(data: &Node, reason: String) -> TmxError {
+    TmxError::MalformedData {
+        element: data.name.clone(),
+        reason,
+        position: data.position,
+    }
+}
+
+fn unsupported(data: &Node, attribute: &str, value: &str) -> TmxError {
+    TmxError::UnsupportedEncoding {
+        element: data.name.clone(),
+        attribute: attribute.to_string(),
+        value: value.to_string(),
+        position: data.position,
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use std::collections::HashMap;
+    use std::io::Write;
+
+    use super::*;
+    use super::flate2::Compression;
+    use super::flate2::write::{GzEncoder, ZlibEncoder};
+    use super::super::xml::common::TextPosition;
+
+    const TILES: [u32; 6] = [1605, 0, 3, 2147483649, 42, 7];
+
+    fn data_node(attributes: &[(&str, &str)], content: &str) -> Node {
+        Node {
+            name: "data".to_string(),
+            attributes: attributes.iter().map(|&(k, v)| (k.to_string(), v.to_string())).collect::<HashMap<_, _>>(),
+            children: Vec::new(),
+            content: content.to_string(),
+            position: TextPosition::new(),
+        }
+    }
+
+    fn raw_bytes() -> Vec<u8> {
+        TILES.iter().flat_map(|t| t.to_le_bytes().to_vec()).collect()
+    }
+
+    #[test]
+    fn decode_csv_data() {
+        let node = data_node(&[("encoding", "csv")], "1605,0,3,\n2147483649,42,7");
+        assert_eq!(decode_tile_data(&node, 6).unwrap(), TILES.to_vec());
+    }
+
+    #[test]
+    fn decode_uncompressed_base64() {
+        let node = data_node(&[("encoding", "base64")], &STANDARD.encode(raw_bytes()));
+        assert_eq!(decode_tile_data(&node, 6).unwrap(), TILES.to_vec());
+    }
+
+    #[test]
+    fn decode_zlib_base64() {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&raw_bytes()).unwrap();
+        let content = format!("\n   {}\n  ", STANDARD.encode(encoder.finish().unwrap()));
+
+        let node = data_node(&[("encoding", "base64"), ("compression", "zlib")], &content);
+        assert_eq!(decode_tile_data(&node, 6).unwrap(), TILES.to_vec());
+    }
+
+    #[test]
+    fn decode_gzip_base64() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&raw_bytes()).unwrap();
+
+        let node = data_node(&[("encoding", "base64"), ("compression", "gzip")], &STANDARD.encode(encoder.finish().unwrap()));
+        assert_eq!(decode_tile_data(&node, 6).unwrap(), TILES.to_vec());
+    }
+
+    #[test]
+    fn decode_zstd_base64() {
+        let compressed = zstd::stream::encode_all(&raw_bytes()[..], 0).unwrap();
+
+        let node = data_node(&[("encoding", "base64"), ("compression", "zstd")], &STANDARD.encode(compressed));
+        assert_eq!(decode_tile_data(&node, 6).unwrap(), TILES.to_vec());
+    }
+
+    #[test]
+    fn decode_xml_tiles() {
+        let mut node = data_node(&[], "");
+        for &gid in TILES.iter() {
+            let mut tile = data_node(&[], "");
+            tile.name = "tile".to_string();
+            if gid != 0 {
+                tile.attributes.insert("gid".to_string(), gid.to_string());
+            }
+            node.children.push(tile);
+        }
+        assert_eq!(decode_tile_data(&node, 6).unwrap(), TILES.to_vec());
+    }
+
+    #[test]
+    fn decode_rejects_bad_data() {
+        let node = data_node(&[("encoding", "base64")], &STANDARD.encode(&raw_bytes()[..5]));
+        match decode_tile_data(&node, 6) {
+            Err(TmxError::MalformedData { .. }) => {}
+            other => panic!("expected malformed data, got {:?}", other),
+        }
+
+        let node = data_node(&[("encoding", "base64"), ("compression", "lzma")], "");
+        match decode_tile_data(&node, 6) {
+            Err(TmxError::UnsupportedEncoding { ref attribute, ref value, .. }) => {
+                assert_eq!(attribute, "compression");
+                assert_eq!(value, "lzma");
+            }
+            other => panic!("expected unsupported encoding, got {:?}", other),
+        }
+
+        let node = data_node(&[("encoding", "base64"), ("compression", "zlib")], &STANDARD.encode(raw_bytes()));
+        match decode_tile_data(&node, 6) {
+            Err(TmxError::MalformedData { .. }) => {}
+            other => panic!("expected malformed data, got {:?}", other),
+        }
+    }
+
+}
@@ -0,0 +1,177 @@
+extern crate serde_json;
+extern crate xml;
+
+use std::error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use self::xml::common::TextPosition;
+
+#[derive(Debug)]
+pub enum TmxError {
+    Io(io::Error),
+    Xml(xml::reader::Error),
+    XmlWrite(xml::writer::Error),
+    Json(serde_json::Error),
+    InvalidJson {
+        element: String,
+        reason: String,
+    },
+    UnsupportedFormat {
+        path: String,
+    },
+    LoadAborted {
+        path: String,
+    },
+    MissingAsset {
+        name: String,
+        reference: String,
+        path: PathBuf,
+    },
+    MissingElement {
+        element: String,
+        child: String,
+        position: TextPosition,
+    },
+    MissingAttribute {
+        element: String,
+        attribute: String,
+        position: TextPosition,
+    },
+    InvalidNumber {
+        element: String,
+        attribute: String,
+        value: String,
+        position: TextPosition,
+    },
+    InvalidValue {
+        element: String,
+        attribute: String,
+        value: String,
+        position: TextPosition,
+    },
+    UnknownPropertyType {
+        element: String,
+        property: String,
+        type_: String,
+        position: TextPosition,
+    },
+    MalformedCsv {
+        element: String,
+        reason: String,
+        position: TextPosition,
+    },
+    MalformedData {
+        element: String,
+        reason: String,
+        position: TextPosition,
+    },
+    /// Tile data with more or fewer cells than the layer or chunk is big.
+    CellCount {
+        element: String,
+        expected: usize,
+        found: usize,
+        position: TextPosition,
+    },
+    ExternalTileset {
+        source: String,
+        error: Box<TmxError>,
+    },
+    UnsupportedEncoding {
+        element: String,
+        attribute: String,
+        value: String,
+        position: TextPosition,
+    },
+}
+
+impl fmt::Display for TmxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TmxError::Io(ref err) => write!(f, "tmx file could not be read: {}", err),
+            TmxError::Xml(ref err) => write!(f, "tmx file is not valid xml: {}", err),
+            TmxError::XmlWrite(ref err) => write!(f, "tmx file could not be written: {}", err),
+            TmxError::Json(ref err) => write!(f, "tmj file is not valid json: {}", err),
+            TmxError::InvalidJson { ref element, ref reason } => {
+                write!(f, "tmj {} {}", element, reason)
+            }
+            TmxError::UnsupportedFormat { ref path } => {
+                write!(f, "'{}' is neither a tmx nor a json map or tileset", path)
+            }
+            TmxError::LoadAborted { ref path } => {
+                write!(f, "map '{}' was not loaded: its worker stopped or the result was taken already", path)
+            }
+            TmxError::MissingAsset { ref name, ref reference, ref path } => {
+                write!(f, "'{}' refers to '{}', but '{}' does not exist", name, reference, path.display())
+            }
+            TmxError::MissingElement { ref element, ref child, position } => {
+                write!(f, "{}: <{}> has no <{}> child", position, element, child)
+            }
+            TmxError::MissingAttribute { ref element, ref attribute, position } => {
+                write!(f, "{}: <{}> is missing attribute '{}'", position, element, attribute)
+            }
+            TmxError::InvalidNumber { ref element, ref attribute, ref value, position } => {
+                write!(f, "{}: <{}> attribute '{}' is not a valid number: '{}'", position, element, attribute, value)
+            }
+            TmxError::InvalidValue { ref element, ref attribute, ref value, position } => {
+                write!(f, "{}: <{}> attribute '{}' has an invalid value: '{}'", position, element, attribute, value)
+            }
+            TmxError::UnknownPropertyType { ref element, ref property, ref type_, position } => {
+                write!(f, "{}: <{}> property '{}' has unknown type '{}'", position, element, property, type_)
+            }
+            TmxError::MalformedCsv { ref element, ref reason, position } => {
+                write!(f, "{}: <{}> has malformed csv data: {}", position, element, reason)
+            }
+            TmxError::MalformedData { ref element, ref reason, position } => {
+                write!(f, "{}: <{}> has malformed tile data: {}", position, element, reason)
+            }
+            TmxError::CellCount { ref element, expected, found, position } => {
+                write!(f, "{}: <{}> has {} cells instead of {}", position, element, found, expected)
+            }
+            TmxError::ExternalTileset { ref source, ref error } => {
+                write!(f, "tileset '{}' could not be loaded: {}", source, error)
+            }
+            TmxError::UnsupportedEncoding { ref element, ref attribute, ref value, position } => {
+                write!(f, "{}: <{}> {} '{}' is not supported", position, element, attribute, value)
+            }
+        }
+    }
+}
+
+impl error::Error for TmxError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            TmxError::Io(ref err) => Some(err),
+            TmxError::Xml(ref err) => Some(err),
+            TmxError::XmlWrite(ref err) => Some(err),
+            TmxError::Json(ref err) => Some(err),
+            TmxError::ExternalTileset { ref error, .. } => Some(&**error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for TmxError {
+    fn from(err: io::Error) -> TmxError {
+        TmxError::Io(err)
+    }
+}
+
+impl From<xml::reader::Error> for TmxError {
+    fn from(err: xml::reader::Error) -> TmxError {
+        TmxError::Xml(err)
+    }
+}
+
+impl From<xml::writer::Error> for TmxError {
+    fn from(err: xml::writer::Error) -> TmxError {
+        TmxError::XmlWrite(err)
+    }
+}
+
+impl From<serde_json::Error> for TmxError {
+    fn from(err: serde_json::Error) -> TmxError {
+        TmxError::Json(err)
+    }
+}
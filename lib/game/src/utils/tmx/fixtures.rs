@@ -0,0 +1,33 @@
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use super::{TmxContent, TilesetCache};
+use super::error::TmxError;
+
+/// Parses a map from memory; external tilesets are looked up in the current directory.
+pub fn parse_tmx(buffer: &[u8]) -> Result<TmxContent, TmxError> {
+    super::parse_tmx(buffer, Path::new("."), &mut TilesetCache::new())
+}
+
+/// A 2x2 orthogonal map with `body` as its content.
+pub fn map(body: &str) -> String {
+    format!(r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.0" orientation="orthogonal" width="2" height="2" tilewidth="16" tileheight="16">
+{}
+</map>"#, body)
+}
+
+/// A fresh directory for the files of one test; remove it at the end of the test.
+pub fn fixture_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("bb2-tmx-{}-{}", name, ::std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+pub fn write_fixture(dir: &Path, file_name: &str, content: &str) -> PathBuf {
+    let path = dir.join(file_name);
+    fs::File::create(&path).unwrap().write_all(content.as_bytes()).unwrap();
+    path
+}
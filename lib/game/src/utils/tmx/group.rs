@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::{Node, TmxEntry};
+use super::common::LayerCommon;
+use super::error::TmxError;
+use super::property::*;
+
+/// A `<group>` layer. Its children are kept in document order, which is
+/// also their draw order, and may be groups themselves.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TmxGroup {
+    pub name: Arc<str>,
+    pub common: LayerCommon,
+    pub properties: HashMap<Arc<str>, Property>,
+    pub children: Vec<TmxEntry>,
+}
+
+impl TmxGroup {
+
+    pub fn from_node(node: &Node, children: Vec<TmxEntry>) -> Result<TmxGroup, TmxError> {
+        Ok(TmxGroup {
+            name: Arc::from(node.attributes.get("name").cloned().unwrap_or_default()),
+            common: LayerCommon::from_node(node)?,
+            properties: Property::properties_from_node(node)?,
+            children,
+        })
+    }
+
+    /// The descendants of this group, depth first.
+    pub fn iter(&self) -> DepthFirst<'_> {
+        DepthFirst::new(&self.children)
+    }
+
+}
+
+/// An entry visited by `DepthFirst`, together with the groups it is nested in.
+#[derive(Debug, Clone)]
+pub struct LayerVisit<'a> {
+    pub entry: &'a TmxEntry,
+    /// The enclosing groups, outermost first.
+    pub parents: Vec<&'a TmxGroup>,
+    /// The entry's layer attributes combined with those of its parents;
+    /// `None` for tilesets.
+    pub common: Option<LayerCommon>,
+}
+
+impl<'a> LayerVisit<'a> {
+
+    pub fn depth(&self) -> usize {
+        self.parents.len()
+    }
+
+    /// Looks `name` up on the entry first and then on its parents, innermost first.
+    pub fn property(&self, name: &str) -> Option<&'a Property> {
+        self.entry.properties()
+            .and_then(|properties| properties.get(name))
+            .or_else(|| self.parents.iter().rev().filter_map(|group| group.properties.get(name)).next())
+    }
+
+}
+
+/// Walks a layer tree depth first, parents before their children.
+pub struct DepthFirst<'a> {
+    stack: Vec<::std::slice::Iter<'a, TmxEntry>>,
+    parents: Vec<(&'a TmxGroup, LayerCommon)>,
+}
+
+impl<'a> DepthFirst<'a> {
+
+    pub fn new(entries: &'a [TmxEntry]) -> DepthFirst<'a> {
+        DepthFirst {
+            stack: vec![entries.iter()],
+            parents: Vec::new(),
+        }
+    }
+
+}
+
+impl<'a> Iterator for DepthFirst<'a> {
+    type Item = LayerVisit<'a>;
+
+    fn next(&mut self) -> Option<LayerVisit<'a>> {
+        loop {
+            let entry = match self.stack.last_mut()?.next() {
+                Some(entry) => entry,
+                None => {
+                    self.stack.pop();
+                    self.parents.pop();
+                    continue;
+                }
+            };
+
+            let common = entry.common().map(|common| match self.parents.last() {
+                Some((_, inherited)) => common.inherit(inherited),
+                None => common.clone(),
+            });
+
+            let visit = LayerVisit {
+                entry,
+                parents: self.parents.iter().map(|&(group, _)| group).collect(),
+                common: common.clone(),
+            };
+
+            if let TmxEntry::Group(ref group) = *entry {
+                self.parents.push((group, common.unwrap_or_default()));
+                self.stack.push(group.children.iter());
+            }
+
+            return Some(visit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use ::noisy_float::prelude::*;
+    use super::super::Color;
+    use super::super::fixtures::{map, parse_tmx};
+
+    #[test]
+    fn read_group_layers() {
+        let xml = map(r##"
+ <group id="1" name="Town" offsetx="10" offsety="4" opacity="0.5">
+  <properties>
+   <property name="zone" value="town"/>
+   <property name="music" value="town.ogg"/>
+  </properties>
+  <layer id="2" name="Ground" width="2" height="2" offsetx="1" opacity="0.5">
+   <properties>
+    <property name="music" value="ground.ogg"/>
+   </properties>
+   <data encoding="csv">1,1,1,1</data>
+  </layer>
+  <group id="3" name="Roofs" visible="0" tintcolor="#ff8080">
+   <layer id="4" name="Roof" width="2" height="2" tintcolor="#80ff80">
+    <data encoding="csv">2,2,2,2</data>
+   </layer>
+  </group>
+ </group>
+ <objectgroup id="5" name="Spawns"/>
+"##);
+        let tmx_content = parse_tmx(xml.as_bytes()).unwrap();
+
+        assert_eq!(tmx_content.entries().len(), 2);
+        let town = tmx_content.get_group("Town").unwrap();
+        assert_eq!(town.children.len(), 2);
+        assert_eq!(tmx_content.get_layer("Roof").unwrap().grid[0][0].gid, 2);
+        assert_eq!(tmx_content.layers().count(), 2);
+
+        let order: Vec<(&str, usize)> = tmx_content.iter().map(|visit| (visit.entry.name().as_ref(), visit.depth())).collect();
+        assert_eq!(order, vec![("Town", 0), ("Ground", 1), ("Roofs", 1), ("Roof", 2), ("Spawns", 0)]);
+
+        let ground = tmx_content.iter().find(|visit| visit.entry.name().as_ref() == "Ground").unwrap();
+        let common = ground.common.as_ref().unwrap();
+        assert_eq!((common.offsetx, common.offsety), (r32(11.0), r32(4.0)));
+        assert_eq!(common.opacity, r32(0.25));
+        assert_eq!(ground.property("music").unwrap().value.as_str(), Some("ground.ogg"));
+        assert_eq!(ground.property("zone").unwrap().value.as_str(), Some("town"));
+        assert!(ground.property("missing").is_none());
+
+        let roof = tmx_content.iter().find(|visit| visit.entry.name().as_ref() == "Roof").unwrap();
+        let common = roof.common.as_ref().unwrap();
+        assert!(!common.visible);
+        assert_eq!(common.tint_color, Some(Color::new(128, 128, 64, 255)));
+        let parents: Vec<&str> = roof.parents.iter().map(|group| group.name.as_ref()).collect();
+        assert_eq!(parents, vec!["Town", "Roofs"]);
+
+        let visible: Vec<&str> = tmx_content.visible_layers().map(|entry| entry.name().as_ref()).collect();
+        assert_eq!(visible, vec!["Ground", "Spawns"]);
+    }
+
+}
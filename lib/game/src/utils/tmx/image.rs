@@ -0,0 +1,49 @@
+use super::Node;
+use super::color::Color;
+use super::error::TmxError;
+
+/// An `<image>` element, as used by tilesets and image layers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TmxImage {
+    pub source: String,
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+    pub trans: Option<Color>,
+}
+
+impl TmxImage {
+
+    pub fn from_node(node: &Node) -> Result<TmxImage, TmxError> {
+
+        let mut source = None;
+        let mut width = None;
+        let mut height = None;
+        let mut trans = None;
+
+        for (key, value) in &node.attributes {
+            match key.as_ref() {
+                "source" => {
+                    source = Some(value.clone());
+                }
+                "width" => {
+                    width = Some(node.parse::<usize>(key, value)?);
+                }
+                "height" => {
+                    height = Some(node.parse::<usize>(key, value)?);
+                }
+                "trans" => {
+                    trans = Some(Color::from_node(node, key, value)?);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(TmxImage {
+            source: source.ok_or_else(|| node.missing_attribute("source"))?,
+            width,
+            height,
+            trans,
+        })
+    }
+
+}
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use super::Node;
+use super::common::LayerCommon;
+use super::error::TmxError;
+use super::image::TmxImage;
+use super::property::*;
+
+/// An `<imagelayer>`, e.g. the `MAP_LIGHTMAP_LAYER_*` overlays.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TmxImageLayer {
+    pub name: Arc<str>,
+    pub common: LayerCommon,
+    pub image: Option<TmxImage>,
+    pub repeat_x: bool,
+    pub repeat_y: bool,
+    pub properties: HashMap<Arc<str>, Property>,
+}
+
+impl<'a> TryFrom<&'a Node> for TmxImageLayer {
+    type Error = TmxError;
+
+    fn try_from(node: &'a Node) -> Result<TmxImageLayer, TmxError> {
+
+        let mut name = String::new();
+        let mut repeat_x = false;
+        let mut repeat_y = false;
+
+        for (key, value) in &node.attributes {
+            match key.as_ref() {
+                "name" => {
+                    name.push_str(value);
+                }
+                "repeatx" => {
+                    repeat_x = node.parse::<u8>(key, value)? != 0;
+                }
+                "repeaty" => {
+                    repeat_y = node.parse::<u8>(key, value)? != 0;
+                }
+                _ => {}
+            }
+        }
+
+        let image = match node.children.iter().find(|child| child.name == "image") {
+            Some(image) => Some(TmxImage::from_node(image)?),
+            None => None,
+        };
+
+        Ok(TmxImageLayer {
+            name: Arc::from(name),
+            common: LayerCommon::from_node(node)?,
+            image,
+            repeat_x,
+            repeat_y,
+            properties: Property::properties_from_node(node)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use ::noisy_float::prelude::*;
+    use ::utils::asset_path;
+    use super::super::TmxContent;
+
+    #[test]
+    fn read_lightmap_image_layers() {
+        let town = TmxContent::load(asset_path("maps/town.tmx")).unwrap();
+        assert_eq!(town.image_layers().count(), 4);
+
+        let night = town.get_image_layer("MAP_LIGHTMAP_LAYER_NIGHT").unwrap();
+        let image = night.image.as_ref().unwrap();
+        assert_eq!(image.source, "town_lightmap_night.png");
+        assert_eq!((image.width, image.height), (Some(656), Some(496)));
+        assert_eq!((night.common.offsetx, night.common.offsety), (r32(-8.0), r32(-8.0)));
+        assert_eq!(night.common.opacity, r32(1.0));
+        assert!(!night.common.visible);
+
+        let castle = TmxContent::load(asset_path("maps/castle_of_doom.tmx")).unwrap();
+        let dawn = castle.get_image_layer("MAP_LIGHTMAP_LAYER_DAWN").unwrap();
+        assert_eq!(dawn.common.opacity, r32(0.9));
+        assert_eq!(dawn.image.as_ref().unwrap().source, "castle_of_doom_lightmap_dawn.png");
+
+        let names: Vec<&str> = castle.entries().iter().map(|entry| entry.name().as_ref()).collect();
+        let night = names.iter().position(|&name| name == "MAP_LIGHTMAP_LAYER_NIGHT").unwrap();
+        let particles = names.iter().position(|&name| name == "PARTICLE_EFFECT_SPAWN_LAYER").unwrap();
+        assert!(night < particles);
+    }
+
+}
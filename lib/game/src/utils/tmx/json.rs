@@ -0,0 +1,516 @@
+extern crate serde_json;
+extern crate xml;
+
+use std::collections::HashMap;
+
+use self::serde_json::{Map, Value};
+use self::xml::common::TextPosition;
+
+use super::Node;
+use super::error::TmxError;
+
+type Object = Map<String, Value>;
+
+/// Reads a Tiled JSON map (`.tmj`) into the same element tree a `.tmx`
+/// document produces, so both formats share one model builder.
+pub fn read_map_document(buffer: &[u8]) -> Result<Node, TmxError> {
+    let json: Value = serde_json::from_slice(buffer)?;
+    let mut root = element("root");
+    root.children.push(map_element(as_object("map", &json)?)?);
+    Ok(root)
+}
+
+/// Reads a Tiled JSON tileset (`.tsj`), the counterpart of a `.tsx` document.
+pub fn read_tileset_document(buffer: &[u8]) -> Result<Node, TmxError> {
+    let json: Value = serde_json::from_slice(buffer)?;
+    let mut root = element("root");
+    root.children.push(tileset_element(as_object("tileset", &json)?)?);
+    Ok(root)
+}
+
+fn map_element(json: &Object) -> Result<Node, TmxError> {
+    let mut node = element("map");
+    copy_attributes(&mut node, json, &["type", "layers", "tilesets", "properties"]);
+    push_properties(&mut node, json)?;
+
+    for tileset in as_array("map", json.get("tilesets"))? {
+        node.children.push(tileset_element(as_object("tileset", tileset)?)?);
+    }
+
+    push_layers(&mut node, json)?;
+
+    Ok(node)
+}
+
+fn tileset_element(json: &Object) -> Result<Node, TmxError> {
+    let mut node = element("tileset");
+    copy_attributes(&mut node, json, &["type", "image", "imagewidth", "imageheight", "transparentcolor",
+                                       "tileoffset", "terrains", "tiles", "wangsets", "properties"]);
+
+    if let Some(offset) = json.get("tileoffset") {
+        let mut tileoffset = element("tileoffset");
+        copy_attributes(&mut tileoffset, as_object("tileoffset", offset)?, &[]);
+        node.children.push(tileoffset);
+    }
+
+    if let Some(image) = image_element(json) {
+        node.children.push(image);
+    }
+
+    if json.contains_key("terrains") {
+        let mut terraintypes = element("terraintypes");
+        for terrain in as_array("terrains", json.get("terrains"))? {
+            let terrain = as_object("terrain", terrain)?;
+            let mut child = element("terrain");
+            copy_attributes(&mut child, terrain, &["properties"]);
+            push_properties(&mut child, terrain)?;
+            terraintypes.children.push(child);
+        }
+        node.children.push(terraintypes);
+    }
+
+    for tile in as_array("tiles", json.get("tiles"))? {
+        node.children.push(tile_element(as_object("tile", tile)?)?);
+    }
+
+    if json.contains_key("wangsets") {
+        let mut wangsets = element("wangsets");
+        for wang_set in as_array("wangsets", json.get("wangsets"))? {
+            wangsets.children.push(wang_set_element(as_object("wangset", wang_set)?)?);
+        }
+        node.children.push(wangsets);
+    }
+
+    Ok(node)
+}
+
+fn tile_element(json: &Object) -> Result<Node, TmxError> {
+    let mut node = element("tile");
+    copy_attributes(&mut node, json, &["terrain", "properties", "animation", "objectgroup", "image"]);
+    push_properties(&mut node, json)?;
+
+    if let Some(terrain) = json.get("terrain") {
+        // json uses -1 for corners without terrain, tmx leaves them empty
+        let corners: Vec<String> = as_array("terrain", Some(terrain))?.iter()
+            .map(|corner| match corner.as_i64() {
+                Some(idx) if idx >= 0 => idx.to_string(),
+                _ => String::new(),
+            })
+            .collect();
+        node.attributes.insert("terrain".to_string(), corners.join(","));
+    }
+
+    if json.contains_key("animation") {
+        let mut animation = element("animation");
+        for frame in as_array("animation", json.get("animation"))? {
+            let mut child = element("frame");
+            copy_attributes(&mut child, as_object("frame", frame)?, &[]);
+            animation.children.push(child);
+        }
+        node.children.push(animation);
+    }
+
+    if let Some(object_group) = json.get("objectgroup") {
+        node.children.push(object_group_element(as_object("objectgroup", object_group)?)?);
+    }
+
+    Ok(node)
+}
+
+fn wang_set_element(json: &Object) -> Result<Node, TmxError> {
+    let mut node = element("wangset");
+    copy_attributes(&mut node, json, &["colors", "wangtiles", "properties"]);
+    push_properties(&mut node, json)?;
+
+    for color in as_array("colors", json.get("colors"))? {
+        let color = as_object("wangcolor", color)?;
+        let mut child = element("wangcolor");
+        copy_attributes(&mut child, color, &["properties"]);
+        push_properties(&mut child, color)?;
+        node.children.push(child);
+    }
+
+    for tile in as_array("wangtiles", json.get("wangtiles"))? {
+        let tile = as_object("wangtile", tile)?;
+        let mut child = element("wangtile");
+        copy_attributes(&mut child, tile, &["wangid"]);
+        let wangid: Vec<String> = as_array("wangid", tile.get("wangid"))?.iter().map(scalar).collect();
+        child.attributes.insert("wangid".to_string(), wangid.join(","));
+        node.children.push(child);
+    }
+
+    Ok(node)
+}
+
+fn push_layers(node: &mut Node, json: &Object) -> Result<(), TmxError> {
+    for layer in as_array("layers", json.get("layers"))? {
+        let layer = as_object("layer", layer)?;
+        let kind = layer.get("type").and_then(Value::as_str).ok_or_else(|| invalid("layer", "has no type"))?;
+        match kind {
+            "tilelayer" => node.children.push(tile_layer_element(layer)?),
+            "objectgroup" => node.children.push(object_group_element(layer)?),
+            "imagelayer" => node.children.push(image_layer_element(layer)?),
+            "group" => {
+                let mut group = element("group");
+                copy_attributes(&mut group, layer, &["type", "layers", "properties"]);
+                push_properties(&mut group, layer)?;
+                push_layers(&mut group, layer)?;
+                node.children.push(group);
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn tile_layer_element(json: &Object) -> Result<Node, TmxError> {
+    let mut node = element("layer");
+    copy_attributes(&mut node, json, &["type", "data", "chunks", "encoding", "compression", "properties"]);
+    push_properties(&mut node, json)?;
+
+    let mut data = element("data");
+    match json.get("encoding").and_then(Value::as_str) {
+        Some("base64") => {
+            data.attributes.insert("encoding".to_string(), "base64".to_string());
+            if let Some(compression) = json.get("compression").and_then(Value::as_str).filter(|c| !c.is_empty()) {
+                data.attributes.insert("compression".to_string(), compression.to_string());
+            }
+        }
+        _ => {
+            data.attributes.insert("encoding".to_string(), "csv".to_string());
+        }
+    }
+
+    match json.get("chunks") {
+        Some(chunks) => {
+            for chunk in as_array("chunks", Some(chunks))? {
+                let chunk = as_object("chunk", chunk)?;
+                let mut child = element("chunk");
+                copy_attributes(&mut child, chunk, &["data"]);
+                child.content = tile_data(chunk.get("data"))?;
+                data.children.push(child);
+            }
+        }
+        None => data.content = tile_data(json.get("data"))?,
+    }
+
+    node.children.push(data);
+    Ok(node)
+}
+
+/// The tile data as it would appear in a `<data>` element: base64 strings
+/// are passed through, gid arrays become csv.
+fn tile_data(data: Option<&Value>) -> Result<String, TmxError> {
+    match data {
+        Some(Value::String(encoded)) => Ok(encoded.clone()),
+        data => {
+            let gids: Vec<String> = as_array("data", data)?.iter().map(scalar).collect();
+            Ok(gids.join(","))
+        }
+    }
+}
+
+fn object_group_element(json: &Object) -> Result<Node, TmxError> {
+    let mut node = element("objectgroup");
+    copy_attributes(&mut node, json, &["type", "objects", "properties"]);
+    push_properties(&mut node, json)?;
+
+    for object in as_array("objects", json.get("objects"))? {
+        node.children.push(object_element(as_object("object", object)?)?);
+    }
+
+    Ok(node)
+}
+
+fn object_element(json: &Object) -> Result<Node, TmxError> {
+    let mut node = element("object");
+    copy_attributes(&mut node, json, &["properties", "ellipse", "point", "polygon", "polyline", "text", "template"]);
+    push_properties(&mut node, json)?;
+
+    let flag = |key: &str| json.get(key).and_then(Value::as_bool).unwrap_or(false);
+
+    if flag("ellipse") {
+        node.children.push(element("ellipse"));
+    }
+    if flag("point") {
+        node.children.push(element("point"));
+    }
+    for &kind in &["polygon", "polyline"] {
+        if let Some(points) = json.get(kind) {
+            let points: Vec<String> = as_array(kind, Some(points))?.iter()
+                .map(|point| format!("{},{}", scalar(&point["x"]), scalar(&point["y"])))
+                .collect();
+            let mut child = element(kind);
+            child.attributes.insert("points".to_string(), points.join(" "));
+            node.children.push(child);
+        }
+    }
+    if let Some(text) = json.get("text") {
+        let text = as_object("text", text)?;
+        let mut child = element("text");
+        copy_attributes(&mut child, text, &["text"]);
+        child.content = text.get("text").map(scalar).unwrap_or_default();
+        node.children.push(child);
+    }
+
+    // json always writes a size; a tmx object without one and without a
+    // shape is a point, so drop the zero size to read it the same way
+    let sized = ["width", "height"].iter().any(|key| json.get(*key).and_then(Value::as_f64).unwrap_or(0.0) != 0.0);
+    if !sized && node.children.iter().all(|child| child.name == "properties") && !json.contains_key("gid") {
+        node.attributes.remove("width");
+        node.attributes.remove("height");
+    }
+
+    Ok(node)
+}
+
+fn image_layer_element(json: &Object) -> Result<Node, TmxError> {
+    let mut node = element("imagelayer");
+    copy_attributes(&mut node, json, &["type", "image", "imagewidth", "imageheight", "transparentcolor", "properties"]);
+    push_properties(&mut node, json)?;
+
+    if let Some(image) = image_element(json) {
+        node.children.push(image);
+    }
+
+    Ok(node)
+}
+
+fn image_element(json: &Object) -> Option<Node> {
+    let source = json.get("image").and_then(Value::as_str).filter(|source| !source.is_empty())?;
+    let mut image = element("image");
+    image.attributes.insert("source".to_string(), source.to_string());
+    for &(key, attribute) in &[("imagewidth", "width"), ("imageheight", "height"), ("transparentcolor", "trans")] {
+        if let Some(value) = json.get(key) {
+            image.attributes.insert(attribute.to_string(), scalar(value));
+        }
+    }
+    Some(image)
+}
+
+fn push_properties(node: &mut Node, json: &Object) -> Result<(), TmxError> {
+    let properties = match json.get("properties") {
+        Some(properties) => properties,
+        None => return Ok(()),
+    };
+
+    let mut list = element("properties");
+    for property in as_array("properties", Some(properties))? {
+        let property = as_object("property", property)?;
+        let mut child = element("property");
+        copy_attributes(&mut child, property, &["value"]);
+        match property.get("value") {
+            Some(Value::Object(members)) => push_class_members(&mut child, members),
+            Some(Value::Bool(value)) => {
+                child.attributes.insert("value".to_string(), value.to_string());
+            }
+            Some(value) => {
+                child.attributes.insert("value".to_string(), scalar(value));
+            }
+            None => {}
+        }
+        list.children.push(child);
+    }
+
+    node.children.push(list);
+    Ok(())
+}
+
+/// Class values only list their members' values, so the member types are
+/// inferred from the json types.
+fn push_class_members(node: &mut Node, members: &Object) {
+    let mut properties = element("properties");
+    for (name, value) in members {
+        let mut child = element("property");
+        child.attributes.insert("name".to_string(), name.clone());
+        let type_ = match *value {
+            Value::Bool(value) => {
+                child.attributes.insert("value".to_string(), value.to_string());
+                "bool"
+            }
+            Value::Number(ref number) if number.is_f64() => {
+                child.attributes.insert("value".to_string(), number.to_string());
+                "float"
+            }
+            Value::Number(ref number) => {
+                child.attributes.insert("value".to_string(), number.to_string());
+                "int"
+            }
+            Value::Object(ref members) => {
+                push_class_members(&mut child, members);
+                "class"
+            }
+            ref value => {
+                child.attributes.insert("value".to_string(), scalar(value));
+                "string"
+            }
+        };
+        child.attributes.insert("type".to_string(), type_.to_string());
+        properties.children.push(child);
+    }
+    node.children.push(properties);
+}
+
+/// Copies the scalar members of `json` as attributes; booleans become the
+/// `0`/`1` flags tmx uses.
+fn copy_attributes(node: &mut Node, json: &Object, skip: &[&str]) {
+    for (key, value) in json {
+        if skip.contains(&key.as_str()) {
+            continue;
+        }
+        match *value {
+            Value::Bool(flag) => {
+                node.attributes.insert(key.clone(), if flag { "1" } else { "0" }.to_string());
+            }
+            Value::Number(_) | Value::String(_) => {
+                node.attributes.insert(key.clone(), scalar(value));
+            }
+            _ => {}
+        }
+    }
+}
+
+fn scalar(value: &Value) -> String {
+    match *value {
+        Value::String(ref value) => value.clone(),
+        Value::Null => String::new(),
+        ref value => value.to_string(),
+    }
+}
+
+fn element(name: &str) -> Node {
+    Node {
+        name: name.to_string(),
+        attributes: HashMap::new(),
+        children: Vec::new(),
+        content: String::new(),
+        position: TextPosition::new(),
+    }
+}
+
+fn as_object<'a>(element: &str, value: &'a Value) -> Result<&'a Object, TmxError> {
+    value.as_object().ok_or_else(|| invalid(element, "is not an object"))
+}
+
+/// A missing array reads as an empty one, as Tiled omits empty lists.
+fn as_array<'a>(element: &str, value: Option<&'a Value>) -> Result<&'a [Value], TmxError> {
+    match value {
+        Some(value) => value.as_array().map(|array| &array[..]).ok_or_else(|| invalid(element, "is not an array")),
+        None => Ok(&[]),
+    }
+}
+
+fn invalid(element: &str, reason: &str) -> TmxError {
+    TmxError::InvalidJson {
+        element: element.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use ::noisy_float::prelude::*;
+    use super::*;
+    use ::utils::asset_path;
+    use ::world::Point;
+    use super::super::{Color, HAlign, ObjectShape, PropertyEnum, TileRef, load_map};
+    use super::super::fixtures::{fixture_dir, write_fixture};
+
+    use std::fs;
+
+    #[test]
+    fn load_json_town_equals_tmx_town() {
+        let tmx = load_map(asset_path("maps/town.tmx")).unwrap();
+        let tmj = load_map(asset_path("maps/town.tmj")).unwrap();
+        assert_eq!(tmj, tmx);
+        assert_eq!(tmj.get_object_group("MAP_SPAWNS_LAYER").unwrap().objects.get(&99).unwrap().shape, ObjectShape::Point);
+    }
+
+    #[test]
+    fn load_json_map_features() {
+        let dir = fixture_dir("json");
+        write_fixture(&dir, "tiles.tsj", r##"{
+ "name": "tiles", "tilewidth": 16, "tileheight": 16, "tilecount": 4, "columns": 2,
+ "image": "tiles.png", "imagewidth": 32, "imageheight": 32, "transparentcolor": "#ff00ff",
+ "tiles": [
+  {"id": 1, "type": "water", "probability": 0.5, "terrain": [0, -1, 0, 0],
+   "animation": [{"tileid": 1, "duration": 100}, {"tileid": 2, "duration": 150}],
+   "properties": [{"name": "Penalty", "type": "float", "value": 2.5}]}
+ ],
+ "terrains": [{"name": "sea", "tile": 1}]
+}"##);
+        let path = write_fixture(&dir, "map.tmj", r##"{
+ "version": "1.10", "orientation": "orthogonal", "renderorder": "right-down",
+ "width": 2, "height": 2, "tilewidth": 16, "tileheight": 16, "infinite": false, "nextobjectid": 5,
+ "properties": [
+  {"name": "music", "type": "file", "value": "town.ogg"},
+  {"name": "spawn", "type": "class", "propertytype": "Spawn", "value": {"count": 3, "boss": false, "rate": 0.5}}
+ ],
+ "tilesets": [{"firstgid": 1, "source": "tiles.tsj"}],
+ "layers": [
+  {"type": "group", "name": "Town", "offsetx": 4, "visible": true, "layers": [
+   {"type": "tilelayer", "name": "Ground", "width": 2, "height": 2, "data": [1, 2, 3, 2147483652]}
+  ]},
+  {"type": "objectgroup", "name": "Objects", "visible": false, "objects": [
+   {"id": 1, "name": "start", "x": 8, "y": 8, "width": 0, "height": 0},
+   {"id": 2, "x": 0, "y": 0, "width": 0, "height": 0, "polygon": [{"x": 0, "y": 0}, {"x": 16, "y": 0}, {"x": 8, "y": 12.5}]},
+   {"id": 3, "x": 0, "y": 0, "width": 32, "height": 16, "text": {"text": "Hello", "wrap": true, "halign": "center"}},
+   {"id": 4, "gid": 2, "x": 0, "y": 16, "width": 16, "height": 16, "rotation": 90}
+  ]}
+ ]
+}"##);
+
+        let content = load_map(&path).unwrap();
+
+        let tileset = content.get_tileset("tiles").unwrap();
+        assert_eq!(tileset.source, Some("tiles.tsj".to_string()));
+        assert_eq!(tileset.image.as_ref().unwrap().trans, Some(Color::new(255, 0, 255, 255)));
+        assert_eq!(tileset.terrain_types[0].tile, Some(1));
+        let water = tileset.tile(2).unwrap();
+        assert_eq!(water.class, "water");
+        assert_eq!(water.terrain, Some([Some(0), None, Some(0), Some(0)]));
+        assert_eq!(water.animation.as_ref().unwrap().frames[1].tileid, 2);
+        assert_eq!(tileset.property(2, "Penalty"), Some(&PropertyEnum::Float(r32(2.5))));
+
+        assert_eq!(content.map.property("music"), Some(&PropertyEnum::File("town.ogg".to_string())));
+        let spawn = content.map.property("spawn").unwrap().as_class().unwrap();
+        assert_eq!(spawn.get("count").unwrap().value, PropertyEnum::Int(3));
+        assert_eq!(spawn.get("boss").unwrap().value, PropertyEnum::Bool(false));
+        assert_eq!(spawn.get("rate").unwrap().value, PropertyEnum::Float(r32(0.5)));
+
+        assert_eq!(content.get_group("Town").unwrap().common.offsetx, r32(4.0));
+        let ground = content.get_layer("Ground").unwrap();
+        assert_eq!(ground.grid[1][1], TileRef { gid: 4, flip_h: true, ..TileRef::default() });
+
+        let objects = &content.get_object_group("Objects").unwrap();
+        assert!(!objects.common.visible);
+        assert_eq!(objects.objects[&1].shape, ObjectShape::Point);
+        match objects.objects[&2].shape {
+            ObjectShape::Polygon(ref points) => assert_eq!(points[2], Point::new(r32(8.0), r32(12.5))),
+            ref shape => panic!("expected a polygon, got {:?}", shape),
+        }
+        match objects.objects[&3].shape {
+            ObjectShape::Text(ref text) => {
+                assert_eq!(text.text, "Hello");
+                assert!(text.wrap);
+                assert_eq!(text.halign, HAlign::Center);
+            }
+            ref shape => panic!("expected text, got {:?}", shape),
+        }
+        assert_eq!(objects.objects[&4].shape, ObjectShape::Tile);
+        assert_eq!(objects.objects[&4].rotation, r32(90.0));
+
+        match load_map(dir.join("map.png")) {
+            Err(TmxError::UnsupportedFormat { .. }) => {}
+            other => panic!("expected an unsupported format, got {:?}", other),
+        }
+        match load_map(write_fixture(&dir, "broken.tmj", r#"{"width": 2, "layers": {}}"#)) {
+            Err(TmxError::InvalidJson { ref element, .. }) => assert_eq!(element, "layers"),
+            other => panic!("expected invalid json, got {:?}", other),
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+}
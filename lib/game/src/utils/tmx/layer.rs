@@ -1,55 +1,203 @@
 
 use super::Node;
+use super::xml::common::TextPosition;
+use super::chunk::ChunkedGrid;
+use super::common::LayerCommon;
+use super::data::decode_tile_data;
+use super::error::TmxError;
+use super::property::*;
 
-use std::rc::Rc;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::sync::Arc;
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct TmxLayer {
-    pub name: Rc<String>,
+    pub name: Arc<str>,
+    pub common: LayerCommon,
     pub width: usize,
     pub height: usize,
+    /// The tiles of a finite layer; empty for layers of infinite maps.
     pub grid: Grid,
+    /// The tiles of a layer of an infinite map.
+    pub chunks: Option<ChunkedGrid>,
+    pub properties: HashMap<Arc<str>, Property>,
 }
 
-impl<'a> From<&'a Node> for TmxLayer {
-    fn from(node: &Node) -> TmxLayer {
+impl TmxLayer {
 
-        let mut width = 0;
-        let mut height = 0;
+    /// The tile at `x`/`y`, for finite and infinite layers alike.
+    pub fn get(&self, x: i32, y: i32) -> Option<TileRef> {
+        match self.chunks {
+            Some(ref chunks) => chunks.get(x, y),
+            None => self.grid.get(x, y),
+        }
+    }
+
+}
+
+impl<'a> TryFrom<&'a Node> for TmxLayer {
+    type Error = TmxError;
+
+    fn try_from(node: &'a Node) -> Result<TmxLayer, TmxError> {
+        let mut builder = LayerBuilder::new(node)?;
+        for child in node.children.iter() {
+            builder.child(child)?;
+        }
+        builder.finish(node)
+    }
+}
+
+/// Builds a `TmxLayer` from the attributes of a `<layer>` and then its
+/// children one at a time, so a streaming reader can drop each child
+/// once it is consumed.
+pub struct LayerBuilder {
+    position: TextPosition,
+    name: String,
+    width: Option<usize>,
+    height: Option<usize>,
+    tiles: Option<(Grid, Option<ChunkedGrid>)>,
+    properties: HashMap<Arc<str>, Property>,
+}
+
+impl LayerBuilder {
+
+    pub fn new(node: &Node) -> Result<LayerBuilder, TmxError> {
+
+        let mut width = None;
+        let mut height = None;
         let mut name = String::new();
 
-        for (ref key, ref value) in &node.attributes {
+        for (key, value) in &node.attributes {
             match key.as_ref() {
                 "width" => {
-                    width = value.parse::<usize>().unwrap();
+                    width = Some(node.parse::<usize>(key, value)?);
                 }
                 "height" => {
-                    height = value.parse::<usize>().unwrap();
+                    height = Some(node.parse::<usize>(key, value)?);
                 }
                 "name" => {
                     name.clear();
-                    name.push_str(value.clone());
+                    name.push_str(value);
                 }
                 _ => {}
             };
         }
 
-        assert!(width > 0);
-        assert!(height > 0);
+        Ok(LayerBuilder {
+            position: node.position,
+            name,
+            width,
+            height,
+            tiles: None,
+            properties: HashMap::new(),
+        })
+    }
+
+    pub fn child(&mut self, child: &Node) -> Result<(), TmxError> {
+        match child.name.as_ref() {
+            "properties" => Property::read_properties(child, &mut self.properties),
+            "data" if self.tiles.is_none() => {
+                self.tiles = Some(self.data(child)?);
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
 
-        let mut grid = Grid::new(width, height);
+    fn data(&mut self, data: &Node) -> Result<(Grid, Option<ChunkedGrid>), TmxError> {
 
-        for (idx, str) in node.children[0].content.split(",").enumerate() {
-            let col = idx % grid.width;
-            let row = idx / grid.width;
-            grid[row][col] = str.trim().parse::<usize>().unwrap();
+        if data.children.iter().any(|child| child.name == "chunk") {
+            let chunks = ChunkedGrid::from_node(data)?;
+            let (_, _, chunks_width, chunks_height) = chunks.bounds();
+            self.width = self.width.or(Some(chunks_width));
+            self.height = self.height.or(Some(chunks_height));
+            return Ok((Grid::new(0, 0), Some(chunks)));
         }
 
-        TmxLayer {
-            name: Rc::new(name),
+        let width = self.width.ok_or_else(|| self.missing_attribute("width"))?;
+        let height = self.height.ok_or_else(|| self.missing_attribute("height"))?;
+
+        let cells = width.checked_mul(height).ok_or_else(|| TmxError::MalformedData {
+            element: data.name.clone(),
+            reason: format!("a {}x{} layer is too large", width, height),
+            position: data.position,
+        })?;
+
+        let grid = Grid {
             width,
-            height,
+            data: decode_tile_data(data, cells)?.into_iter().map(TileRef::from).collect(),
+        };
+
+        Ok((grid, None))
+    }
+
+    fn missing_attribute(&self, attribute: &str) -> TmxError {
+        TmxError::MissingAttribute {
+            element: "layer".to_string(),
+            attribute: attribute.to_string(),
+            position: self.position,
+        }
+    }
+
+    pub fn finish(self, node: &Node) -> Result<TmxLayer, TmxError> {
+        let (grid, chunks) = self.tiles.ok_or_else(|| node.missing_element("data"))?;
+
+        Ok(TmxLayer {
+            name: Arc::from(self.name),
+            common: LayerCommon::from_node(node)?,
+            width: self.width.ok_or_else(|| node.missing_attribute("width"))?,
+            height: self.height.ok_or_else(|| node.missing_attribute("height"))?,
             grid,
+            chunks,
+            properties: self.properties,
+        })
+    }
+
+}
+
+const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+const ROTATED_HEXAGONAL_120: u32 = 0x1000_0000;
+const FLAGS: u32 = FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY | ROTATED_HEXAGONAL_120;
+
+/// A cell of a tile layer: the global tile id with Tiled's flip flags split off.
+/// A `gid` of 0 marks an empty cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TileRef {
+    pub gid: usize,
+    pub flip_h: bool,
+    pub flip_v: bool,
+    pub flip_diag: bool,
+    pub hex_rotate: bool,
+}
+
+impl TileRef {
+
+    pub fn is_empty(&self) -> bool {
+        self.gid == 0
+    }
+
+    pub fn raw(&self) -> u32 {
+        let mut raw = self.gid as u32;
+        if self.flip_h { raw |= FLIPPED_HORIZONTALLY; }
+        if self.flip_v { raw |= FLIPPED_VERTICALLY; }
+        if self.flip_diag { raw |= FLIPPED_DIAGONALLY; }
+        if self.hex_rotate { raw |= ROTATED_HEXAGONAL_120; }
+        raw
+    }
+
+}
+
+impl From<u32> for TileRef {
+    fn from(raw: u32) -> TileRef {
+        TileRef {
+            gid: (raw & !FLAGS) as usize,
+            flip_h: raw & FLIPPED_HORIZONTALLY != 0,
+            flip_v: raw & FLIPPED_VERTICALLY != 0,
+            flip_diag: raw & FLIPPED_DIAGONALLY != 0,
+            hex_rotate: raw & ROTATED_HEXAGONAL_120 != 0,
         }
     }
 }
@@ -57,29 +205,80 @@ impl<'a> From<&'a Node> for TmxLayer {
 #[derive(Debug, PartialEq, Eq)]
 pub struct Grid {
     width: usize,
-    data: Vec<usize>,
+    data: Vec<TileRef>,
 }
 
 impl Grid {
-    fn new(width: usize, height: usize) -> Grid {
+
+    pub fn new(width: usize, height: usize) -> Grid {
         Grid {
             width,
-            data: vec![0; width * height],
+            data: vec![TileRef::default(); width * height],
+        }
+    }
+
+    /// The tile at `x`/`y`, or `None` outside of the grid.
+    pub fn get(&self, x: i32, y: i32) -> Option<TileRef> {
+        if x < 0 || y < 0 || x as usize >= self.width {
+            return None;
         }
+        self.data.get(y as usize * self.width + x as usize).cloned()
     }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.data.len().checked_div(self.width).unwrap_or(0)
+    }
+
+    pub fn iter(&self) -> ::std::slice::Iter<'_, TileRef> {
+        self.data.iter()
+    }
+
 }
 
 impl ::std::ops::Index<usize> for Grid {
-    type Output = [usize];
-    fn index(&self, row: usize) -> &[usize] {
+    type Output = [TileRef];
+    fn index(&self, row: usize) -> &[TileRef] {
         let start = row * self.width;
         &self.data[start .. start + self.width]
     }
 }
 
 impl ::std::ops::IndexMut<usize> for Grid {
-    fn index_mut(&mut self, row: usize) -> &mut [usize] {
+    fn index_mut(&mut self, row: usize) -> &mut [TileRef] {
         let start = row * self.width;
         &mut self.data[start .. start + self.width]
     }
 }
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use super::super::fixtures::{map, parse_tmx};
+
+    #[test]
+    fn decode_flip_flags() {
+        let tile = TileRef::from(0x8000_0000 | 1605);
+        assert_eq!(tile.gid, 1605);
+        assert!(tile.flip_h && !tile.flip_v && !tile.flip_diag && !tile.hex_rotate);
+
+        let tile = TileRef::from(0x7000_0000 | 3);
+        assert_eq!(tile.gid, 3);
+        assert!(!tile.flip_h && tile.flip_v && tile.flip_diag && tile.hex_rotate);
+        assert_eq!(tile.raw(), 0x7000_0003);
+
+        let xml = map(r#" <layer name="Ground" width="2" height="2">
+  <data encoding="csv">2147483649,0,1073741826,3</data>
+ </layer>"#);
+        let tmx_content = parse_tmx(xml.as_bytes()).unwrap();
+        let layer = tmx_content.get_layer("Ground").unwrap();
+        assert_eq!(layer.grid[0][0], TileRef { gid: 1, flip_h: true, ..TileRef::default() });
+        assert!(layer.grid[0][1].is_empty());
+        assert_eq!(layer.grid[1][0], TileRef { gid: 2, flip_v: true, ..TileRef::default() });
+    }
+
+}
@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::Node;
+use super::color::Color;
+use super::error::TmxError;
+use super::property::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    Orthogonal,
+    Isometric,
+    Staggered,
+    Hexagonal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderOrder {
+    RightDown,
+    RightUp,
+    LeftDown,
+    LeftUp,
+}
+
+/// The attributes and properties of the `<map>` root element.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TmxMap {
+    pub version: String,
+    pub tiled_version: Option<String>,
+    pub orientation: Orientation,
+    pub render_order: RenderOrder,
+    pub width: usize,
+    pub height: usize,
+    pub tilewidth: usize,
+    pub tileheight: usize,
+    /// `None` for maps without a `nextobjectid`, like older Tiled exports.
+    pub next_object_id: Option<usize>,
+    /// `None` for maps saved before Tiled 1.2.
+    pub next_layer_id: Option<usize>,
+    pub infinite: bool,
+    pub background_color: Option<Color>,
+    pub properties: HashMap<Arc<str>, Property>,
+}
+
+impl TmxMap {
+
+    pub fn pixel_width(&self) -> usize {
+        self.width * self.tilewidth
+    }
+
+    pub fn pixel_height(&self) -> usize {
+        self.height * self.tileheight
+    }
+
+    pub fn property(&self, name: &str) -> Option<&PropertyEnum> {
+        self.properties.get(name).map(|property| &property.value)
+    }
+
+    pub fn from_node(node: &Node) -> Result<TmxMap, TmxError> {
+
+        let mut version = String::new();
+        let mut tiled_version = None;
+        let mut orientation = Orientation::Orthogonal;
+        let mut render_order = RenderOrder::RightDown;
+        let mut width = None;
+        let mut height = None;
+        let mut tilewidth = None;
+        let mut tileheight = None;
+        let mut next_object_id = None;
+        let mut next_layer_id = None;
+        let mut infinite = false;
+        let mut background_color = None;
+
+        for (key, value) in &node.attributes {
+            match key.as_ref() {
+                "version" => {
+                    version.push_str(value);
+                }
+                "tiledversion" => {
+                    tiled_version = Some(value.clone());
+                }
+                "orientation" => {
+                    orientation = match value.as_ref() {
+                        "orthogonal" => Orientation::Orthogonal,
+                        "isometric" => Orientation::Isometric,
+                        "staggered" => Orientation::Staggered,
+                        "hexagonal" => Orientation::Hexagonal,
+                        _ => return Err(node.invalid_value(key, value)),
+                    };
+                }
+                "renderorder" => {
+                    render_order = match value.as_ref() {
+                        "right-down" => RenderOrder::RightDown,
+                        "right-up" => RenderOrder::RightUp,
+                        "left-down" => RenderOrder::LeftDown,
+                        "left-up" => RenderOrder::LeftUp,
+                        _ => return Err(node.invalid_value(key, value)),
+                    };
+                }
+                "width" => {
+                    width = Some(node.parse::<usize>(key, value)?);
+                }
+                "height" => {
+                    height = Some(node.parse::<usize>(key, value)?);
+                }
+                "tilewidth" => {
+                    tilewidth = Some(node.parse::<usize>(key, value)?);
+                }
+                "tileheight" => {
+                    tileheight = Some(node.parse::<usize>(key, value)?);
+                }
+                "nextobjectid" => {
+                    next_object_id = Some(node.parse::<usize>(key, value)?);
+                }
+                "nextlayerid" => {
+                    next_layer_id = Some(node.parse::<usize>(key, value)?);
+                }
+                "infinite" => {
+                    infinite = node.parse::<u8>(key, value)? != 0;
+                }
+                "backgroundcolor" => {
+                    background_color = Some(Color::from_node(node, key, value)?);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(TmxMap {
+            version,
+            tiled_version,
+            orientation,
+            render_order,
+            width: width.ok_or_else(|| node.missing_attribute("width"))?,
+            height: height.ok_or_else(|| node.missing_attribute("height"))?,
+            tilewidth: tilewidth.ok_or_else(|| node.missing_attribute("tilewidth"))?,
+            tileheight: tileheight.ok_or_else(|| node.missing_attribute("tileheight"))?,
+            next_object_id,
+            next_layer_id,
+            infinite,
+            background_color,
+            properties: Property::properties_from_node(node)?,
+        })
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use ::utils::asset_path;
+    use super::super::TmxContent;
+    use super::super::fixtures::parse_tmx;
+
+    #[test]
+    fn read_map_header() {
+        let town = TmxContent::load(asset_path("maps/town.tmx")).unwrap();
+        assert_eq!(town.map.orientation, Orientation::Orthogonal);
+        assert_eq!(town.map.render_order, RenderOrder::RightDown);
+        assert_eq!((town.map.width, town.map.height), (40, 30));
+        assert_eq!((town.map.pixel_width(), town.map.pixel_height()), (640, 480));
+        assert_eq!(town.map.next_object_id, Some(188));
+        assert!(!town.map.infinite);
+
+        let topworld = TmxContent::load(asset_path("maps/topworld.tmx")).unwrap();
+        assert_eq!(topworld.map.tiled_version, Some("1.1.2".to_string()));
+        assert_eq!((topworld.map.pixel_width(), topworld.map.pixel_height()), (1200, 1200));
+
+        let castle = TmxContent::load(asset_path("maps/castle_of_doom.tmx")).unwrap();
+        assert_eq!((castle.map.pixel_width(), castle.map.pixel_height()), (512, 1280));
+    }
+
+    #[test]
+    fn read_map_properties_and_background() {
+        let xml = r##"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.0" orientation="isometric" renderorder="left-up" width="2" height="2" tilewidth="32" tileheight="16" backgroundcolor="#80ff0000" infinite="0" nextobjectid="3">
+ <properties>
+  <property name="music" value="Magic Town_0.mp3"/>
+ </properties>
+</map>"##;
+        let tmx_content = parse_tmx(xml.as_bytes()).unwrap();
+        assert_eq!(tmx_content.map.orientation, Orientation::Isometric);
+        assert_eq!(tmx_content.map.render_order, RenderOrder::LeftUp);
+        assert_eq!(tmx_content.map.background_color, Some(Color::new(255, 0, 0, 128)));
+        assert_eq!(tmx_content.map.property("music"), Some(&PropertyEnum::String("Magic Town_0.mp3".to_string())));
+
+        match parse_tmx(xml.replace("isometric", "diagonal").as_bytes()) {
+            Err(TmxError::InvalidValue { ref attribute, .. }) => assert_eq!(attribute, "orientation"),
+            other => panic!("expected invalid value, got {:?}", other),
+        }
+    }
+
+}
@@ -1,53 +1,286 @@
 extern crate xml;
 
+mod animation;
+mod assets;
+mod background;
+mod chunk;
+mod color;
+mod common;
+mod data;
+mod error;
+#[cfg(test)]
+mod fixtures;
+mod group;
+mod image;
+mod imagelayer;
+mod json;
+mod map;
 mod property;
+mod resolver;
+mod stream;
+mod terrain;
 mod tileset;
+mod tsx;
+mod validate;
+mod wang;
+mod writer;
 mod layer;
 mod objectgroup;
 mod node;
 
-use std::fs::File;
-use std::io::{Read};
-use std::path::Path;
 use std::collections::HashMap;
-use std::rc::Rc;
+use std::convert::TryFrom;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use self::xml::common::TextPosition;
 use self::xml::reader::{ParserConfig};
 
-use self::property::*;
-use self::tileset::*;
-use self::layer::*;
-use self::objectgroup::*;
+use ::world::Area;
+
+pub use self::animation::{Animation, Frame, TileAnimator};
+pub use self::assets::{AssetKind, AssetPath};
+pub use self::background::{MapLoad, load_map_in_background, load_map_in_background_with_cache};
+pub use self::chunk::{Chunk, ChunkedGrid};
+pub use self::color::Color;
+pub use self::common::LayerCommon;
+pub use self::error::TmxError;
+pub use self::group::{DepthFirst, LayerVisit, TmxGroup};
+pub use self::image::TmxImage;
+pub use self::imagelayer::TmxImageLayer;
+pub use self::map::*;
+pub use self::property::*;
+pub use self::resolver::{FileResolver, MemoryResolver, Resolver, normalize};
+pub use self::terrain::{Terrain, parse_terrain};
+pub use self::tileset::*;
+pub use self::tsx::TilesetCache;
+pub use self::validate::{Check, Diagnostic, Severity, validate, validate_file, validate_with};
+pub use self::wang::*;
+pub use self::writer::DataEncoding;
+pub use self::layer::*;
+pub use self::objectgroup::*;
 use self::node::*;
 
+/// A parsed map. Entries are kept in document order, which is also the draw
+/// order of the layers, with group layers holding their children; names may
+/// repeat and are looked up through an index of paths into that tree.
 #[derive(Debug, PartialEq, Eq)]
 pub struct TmxContent {
-    pub entries: HashMap<Rc<String>, TmxEntry>,
+    pub map: TmxMap,
+    entries: Vec<TmxEntry>,
+    names: HashMap<String, Vec<Vec<usize>>>,
+    base_dir: PathBuf,
 }
 
 impl TmxContent {
 
-    pub fn from_file(file_name: &str) -> TmxContent {
+    pub fn new(map: TmxMap, entries: Vec<TmxEntry>) -> TmxContent {
+        let mut names = HashMap::new();
+        index_names(&entries, &mut Vec::new(), &mut names);
+        TmxContent {
+            map,
+            entries,
+            names,
+            base_dir: PathBuf::new(),
+        }
+    }
 
-        let path = Path::new(file_name);
-        let mut f = File::open(path).expect("tmx file could not be opend");
-        let mut buffer = Vec::<u8>::new();
+    /// Sets the directory the map's relative paths are resolved against.
+    pub fn with_base_dir<P: AsRef<Path>>(mut self, base_dir: P) -> TmxContent {
+        self.base_dir = normalize(base_dir.as_ref());
+        self
+    }
 
-        f.read_to_end(&mut buffer).expect("tmx file could not be read");
+    /// The directory the map was loaded from, normalized.
+    pub fn base_dir(&self) -> &Path {
+        &self.base_dir
+    }
 
-        let mut reader = parser_config().create_reader(&buffer[..]);
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<TmxContent, TmxError> {
+        TmxContent::load_with_cache(path, &mut TilesetCache::new())
+    }
+
+    /// Like `load`, but external tilesets are looked up in and added to `cache`,
+    /// so loading several maps that share `.tsx` files reads each only once.
+    /// The map itself is read through the cache's resolver as well.
+    pub fn load_with_cache<P: AsRef<Path>>(path: P, cache: &mut TilesetCache) -> Result<TmxContent, TmxError> {
 
-        let mut root = Node {
-            name: "root".to_string(),
-            attributes: HashMap::new(),
-            children: Vec::new(),
-            content: "".to_string(),
-        };
+        let path = path.as_ref();
+        let resolver = cache.resolver();
+        let source = resolver.open(path)?;
 
-        read_node_elements(&mut root, &mut reader);
+        parse_tmx(source, base_dir(path), cache)
+    }
 
-        create_tmx_content(& root)
+    /// Loads a map Tiled exported as JSON (`.tmj`).
+    pub fn load_json<P: AsRef<Path>>(path: P) -> Result<TmxContent, TmxError> {
+        TmxContent::load_json_with_cache(path, &mut TilesetCache::new())
     }
 
+    pub fn load_json_with_cache<P: AsRef<Path>>(path: P, cache: &mut TilesetCache) -> Result<TmxContent, TmxError> {
+
+        let path = path.as_ref();
+        let resolver = cache.resolver();
+        let source = resolver.open(path)?;
+
+        parse_tmj(source, base_dir(path), cache)
+    }
+
+    /// The top level entries; the contents of groups are in `TmxGroup::children`.
+    pub fn entries(&self) -> &[TmxEntry] {
+        &self.entries
+    }
+
+    /// All entries, including those nested in groups, depth first.
+    pub fn iter(&self) -> DepthFirst<'_> {
+        DepthFirst::new(&self.entries)
+    }
+
+    /// All entries called `name` at any depth, in document order.
+    pub fn get_all<'a>(&'a self, name: &str) -> impl Iterator<Item = &'a TmxEntry> + 'a {
+        self.names.get(name)
+            .into_iter()
+            .flat_map(move |paths| paths.iter().map(move |path| self.entry_at(path)))
+    }
+
+    fn entry_at(&self, path: &[usize]) -> &TmxEntry {
+        let mut entry = &self.entries[path[0]];
+        for &idx in &path[1 ..] {
+            entry = &entry.as_group().expect("name index points into a group").children[idx];
+        }
+        entry
+    }
+
+    /// The first entry called `name`.
+    pub fn get(&self, name: &str) -> Option<&TmxEntry> {
+        self.get_all(name).next()
+    }
+
+    pub fn layers(&self) -> impl Iterator<Item = &TmxLayer> {
+        self.iter().filter_map(|visit| visit.entry.as_layer())
+    }
+
+    pub fn tilesets(&self) -> impl Iterator<Item = &TmxTileset> {
+        self.entries.iter().filter_map(TmxEntry::as_tileset)
+    }
+
+    pub fn object_groups(&self) -> impl Iterator<Item = &TmxObjectGroup> {
+        self.iter().filter_map(|visit| visit.entry.as_object_group())
+    }
+
+    pub fn image_layers(&self) -> impl Iterator<Item = &TmxImageLayer> {
+        self.iter().filter_map(|visit| visit.entry.as_image_layer())
+    }
+
+    pub fn groups(&self) -> impl Iterator<Item = &TmxGroup> {
+        self.iter().filter_map(|visit| visit.entry.as_group())
+    }
+
+    /// The tile, object and image layers that should be drawn, in draw order.
+    /// A layer inside a hidden group is hidden as well.
+    pub fn visible_layers(&self) -> impl Iterator<Item = &TmxEntry> {
+        self.iter()
+            .filter(|visit| visit.entry.as_group().is_none() && visit.common.as_ref().is_some_and(|common| common.visible))
+            .map(|visit| visit.entry)
+    }
+
+    pub fn get_layer(&self, name: &str) -> Option<&TmxLayer> {
+        self.get_all(name).filter_map(TmxEntry::as_layer).next()
+    }
+
+    pub fn get_tileset(&self, name: &str) -> Option<&TmxTileset> {
+        self.get_all(name).filter_map(TmxEntry::as_tileset).next()
+    }
+
+    pub fn get_object_group(&self, name: &str) -> Option<&TmxObjectGroup> {
+        self.get_all(name).filter_map(TmxEntry::as_object_group).next()
+    }
+
+    pub fn get_image_layer(&self, name: &str) -> Option<&TmxImageLayer> {
+        self.get_all(name).filter_map(TmxEntry::as_image_layer).next()
+    }
+
+    pub fn get_group(&self, name: &str) -> Option<&TmxGroup> {
+        self.get_all(name).filter_map(TmxEntry::as_group).next()
+    }
+
+    /// The tileset owning `gid`, i.e. the one with the highest `firstgid` not above it.
+    pub fn tileset_for_gid(&self, gid: usize) -> Option<&TmxTileset> {
+        if gid == 0 {
+            return None;
+        }
+        self.tilesets()
+            .filter(|tileset| tileset.firstgid <= gid)
+            .max_by_key(|tileset| tileset.firstgid)
+            .filter(|tileset| tileset.contains_gid(gid))
+    }
+
+    /// Resolves a layer cell to its tileset, local tile index and source rectangle.
+    pub fn resolve_tile(&self, tile: &TileRef) -> Option<(&TmxTileset, usize, Area)> {
+        let tileset = self.tileset_for_gid(tile.gid)?;
+        let local_id = tileset.local_id(tile.gid)?;
+        let rect = tileset.source_rect(local_id)?;
+        Some((tileset, local_id, rect))
+    }
+
+}
+
+/// Loads a map in either of Tiled's formats, picked by the file extension:
+/// `.tmx` for XML, `.tmj` or `.json` for JSON.
+pub fn load_map<P: AsRef<Path>>(path: P) -> Result<TmxContent, TmxError> {
+    load_map_with_cache(path, &mut TilesetCache::new())
+}
+
+pub fn load_map_with_cache<P: AsRef<Path>>(path: P, cache: &mut TilesetCache) -> Result<TmxContent, TmxError> {
+    let path = path.as_ref();
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("tmx") => TmxContent::load_with_cache(path, cache),
+        Some("tmj") | Some("json") => TmxContent::load_json_with_cache(path, cache),
+        _ => Err(TmxError::UnsupportedFormat { path: path.display().to_string() }),
+    }
+}
+
+fn base_dir(path: &Path) -> &Path {
+    path.parent().unwrap_or_else(|| Path::new(""))
+}
+
+/// Parses a `.tmx` document in a single streaming pass; relative paths of
+/// external tilesets are resolved against `base_dir`.
+pub fn parse_tmx<R: Read>(source: R, base_dir: &Path, cache: &mut TilesetCache) -> Result<TmxContent, TmxError> {
+    stream::read_map(source, base_dir, cache)
+}
+
+/// Parses a map Tiled exported as JSON; relative paths of external tilesets
+/// are resolved against `base_dir`.
+pub fn parse_tmj<R: Read>(mut source: R, base_dir: &Path, cache: &mut TilesetCache) -> Result<TmxContent, TmxError> {
+    let mut buffer = Vec::<u8>::new();
+    source.read_to_end(&mut buffer)?;
+
+    create_tmx_content(&json::read_map_document(&buffer[..])?, base_dir, cache)
+}
+
+/// Parses a `.tmx` document by reading it into a `Node` tree first. Produces
+/// the same content as `parse_tmx` but holds the whole tree in memory; kept
+/// as the reference the streaming parser is tested and benchmarked against.
+pub fn parse_tmx_document(buffer: &[u8], base_dir: &Path, cache: &mut TilesetCache) -> Result<TmxContent, TmxError> {
+    create_tmx_content(&read_document(buffer)?, base_dir, cache)
+}
+
+fn read_document(buffer: &[u8]) -> Result<Node, TmxError> {
+
+    let mut reader = parser_config().create_reader(buffer);
+
+    let mut root = Node {
+        name: "root".to_string(),
+        attributes: HashMap::new(),
+        children: Vec::new(),
+        content: "".to_string(),
+        position: TextPosition::new(),
+    };
+
+    read_node_elements(&mut root, &mut reader)?;
+
+    Ok(root)
 }
 
 fn parser_config() -> ParserConfig {
@@ -58,31 +291,60 @@ fn parser_config() -> ParserConfig {
         .whitespace_to_characters(true)
 }
 
-fn create_tmx_content(root: &Node) -> TmxContent {
+fn index_names(entries: &[TmxEntry], path: &mut Vec<usize>, names: &mut HashMap<String, Vec<Vec<usize>>>) {
+    for (idx, entry) in entries.iter().enumerate() {
+        path.push(idx);
+        names.entry(entry.name().to_string()).or_default().push(path.clone());
+        if let TmxEntry::Group(ref group) = *entry {
+            index_names(&group.children, path, names);
+        }
+        path.pop();
+    }
+}
+
+fn create_tmx_content(root: &Node, base_dir: &Path, cache: &mut TilesetCache) -> Result<TmxContent, TmxError> {
+
+    let map_node = root.child("map")?;
+    let map = TmxMap::from_node(map_node)?;
+    let entries = create_entries(map_node, base_dir, cache)?;
 
-    let mut entries = HashMap::new();
+    Ok(TmxContent::new(map, entries).with_base_dir(base_dir))
+}
+
+fn create_entries(parent: &Node, base_dir: &Path, cache: &mut TilesetCache) -> Result<Vec<TmxEntry>, TmxError> {
+
+    let mut entries = Vec::new();
 
-    for node in root.children[0].children.iter() {
+    for node in parent.children.iter() {
         match node.name.as_ref() {
             "layer" => {
-                let layer = TmxLayer::from(node);
-                entries.insert(layer.name.clone(), TmxEntry::Layer(layer));
+                entries.push(TmxEntry::Layer(TmxLayer::try_from(node)?));
             }
             "tileset" => {
-                let tileset = TmxTileset::from(node);
-                entries.insert(tileset.name.clone(), TmxEntry::Tileset(tileset));
+                let tileset = match node.attributes.get("source") {
+                    Some(source) => {
+                        let firstgid = node.attributes.get("firstgid").ok_or_else(|| node.missing_attribute("firstgid"))?;
+                        cache.load(base_dir, source, node.parse::<usize>("firstgid", firstgid)?)?
+                    }
+                    None => TmxTileset::try_from(node)?,
+                };
+                entries.push(TmxEntry::Tileset(tileset));
             }
             "objectgroup" => {
-                let object_group = TmxObjectGroup::from(node);
-                entries.insert(object_group.name.clone(), TmxEntry::ObjectGroup(object_group));
+                entries.push(TmxEntry::ObjectGroup(TmxObjectGroup::try_from(node)?));
+            }
+            "imagelayer" => {
+                entries.push(TmxEntry::ImageLayer(TmxImageLayer::try_from(node)?));
+            }
+            "group" => {
+                let children = create_entries(node, base_dir, cache)?;
+                entries.push(TmxEntry::Group(TmxGroup::from_node(node, children)?));
             }
             _ => {}
         }
     }
 
-    TmxContent {
-        entries,
-    }
+    Ok(entries)
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -90,6 +352,80 @@ pub enum TmxEntry {
     Layer(TmxLayer),
     Tileset(TmxTileset),
     ObjectGroup(TmxObjectGroup),
+    ImageLayer(TmxImageLayer),
+    Group(TmxGroup),
+}
+
+impl TmxEntry {
+
+    pub fn name(&self) -> &Arc<str> {
+        match *self {
+            TmxEntry::Layer(ref layer) => &layer.name,
+            TmxEntry::Tileset(ref tileset) => &tileset.name,
+            TmxEntry::ObjectGroup(ref object_group) => &object_group.name,
+            TmxEntry::ImageLayer(ref image_layer) => &image_layer.name,
+            TmxEntry::Group(ref group) => &group.name,
+        }
+    }
+
+    /// The shared layer attributes; `None` for tilesets, which are not layers.
+    pub fn common(&self) -> Option<&LayerCommon> {
+        match *self {
+            TmxEntry::Layer(ref layer) => Some(&layer.common),
+            TmxEntry::Tileset(_) => None,
+            TmxEntry::ObjectGroup(ref object_group) => Some(&object_group.common),
+            TmxEntry::ImageLayer(ref image_layer) => Some(&image_layer.common),
+            TmxEntry::Group(ref group) => Some(&group.common),
+        }
+    }
+
+    /// The custom properties of the entry itself, without inherited ones;
+    /// `None` for tilesets, whose properties belong to their tiles.
+    pub fn properties(&self) -> Option<&HashMap<Arc<str>, Property>> {
+        match *self {
+            TmxEntry::Layer(ref layer) => Some(&layer.properties),
+            TmxEntry::Tileset(_) => None,
+            TmxEntry::ObjectGroup(ref object_group) => Some(&object_group.properties),
+            TmxEntry::ImageLayer(ref image_layer) => Some(&image_layer.properties),
+            TmxEntry::Group(ref group) => Some(&group.properties),
+        }
+    }
+
+    pub fn as_layer(&self) -> Option<&TmxLayer> {
+        match *self {
+            TmxEntry::Layer(ref layer) => Some(layer),
+            _ => None,
+        }
+    }
+
+    pub fn as_tileset(&self) -> Option<&TmxTileset> {
+        match *self {
+            TmxEntry::Tileset(ref tileset) => Some(tileset),
+            _ => None,
+        }
+    }
+
+    pub fn as_object_group(&self) -> Option<&TmxObjectGroup> {
+        match *self {
+            TmxEntry::ObjectGroup(ref object_group) => Some(object_group),
+            _ => None,
+        }
+    }
+
+    pub fn as_image_layer(&self) -> Option<&TmxImageLayer> {
+        match *self {
+            TmxEntry::ImageLayer(ref image_layer) => Some(image_layer),
+            _ => None,
+        }
+    }
+
+    pub fn as_group(&self) -> Option<&TmxGroup> {
+        match *self {
+            TmxEntry::Group(ref group) => Some(group),
+            _ => None,
+        }
+    }
+
 }
 
 #[cfg(test)]
@@ -97,44 +433,186 @@ mod test {
 
     use ::noisy_float::*;
     use super::*;
+    use super::fixtures::{map, parse_tmx};
+    use ::utils::asset_path;
 
     #[test]
     fn read_tmx_file() {
-        let file_name = "../../assets/maps/topworld.tmx";
+        let file_name = asset_path("maps/topworld.tmx");
 
-        let tmx_content = TmxContent::from_file(file_name);
+        let tmx_content = TmxContent::load(file_name).unwrap();
 
-        handle_tmx_entry(tmx_content.entries.get(&"Background_Layer".to_string()).unwrap());
-        handle_tmx_entry(tmx_content.entries.get(&"Floor".to_string()).unwrap());
-        handle_tmx_entry(tmx_content.entries.get(&"MAP_QUEST_DISCOVER_LAYER".to_string()).unwrap());
+        handle_tmx_entry(tmx_content.get("Background_Layer").unwrap());
+        handle_tmx_entry(tmx_content.get("Floor").unwrap());
+        handle_tmx_entry(tmx_content.get("MAP_QUEST_DISCOVER_LAYER").unwrap());
     }
 
     fn handle_tmx_entry(entry: &TmxEntry) {
-        match entry {
-            &TmxEntry::Layer(ref layer) => {
+        match *entry {
+            TmxEntry::Layer(ref layer) => {
                 let data = layer.grid[0][0];
-                assert_eq!(data, 1605);
+                assert_eq!(data.gid, 1605);
             }
-            &TmxEntry::Tileset(ref tileset) => {
-                //let PropertyEnum::Float(got) = tileset.tiles.get(&1161).unwrap().properties.get(&"Penalty".to_string()).unwrap().value;
+            TmxEntry::Tileset(ref tileset) => {
                 let got = tileset.property(1161, "Penalty");
                 let should_be = prelude::r32(1.2);
 
-                match got {
-                    Some(&PropertyEnum::Float(f)) => assert_eq!(f, should_be),
-                    _ => {}
-                };
+                assert_eq!(got, Some(&PropertyEnum::Float(should_be)));
             }
-            &TmxEntry::ObjectGroup(ref object_group) => {
-                let got = object_group.objects.get(&400).unwrap().properties.get(&"taskID".to_string()).unwrap();
-                match &got.value {
-                    &PropertyEnum::String(ref got) => {
-                        assert_eq!(got, "4");
-                    }
-                    _ => assert!(false)
-                }
+            TmxEntry::ObjectGroup(ref object_group) => {
+                let got = object_group.objects.get(&400).unwrap().properties.get("taskID").unwrap();
+                assert_eq!(got.value, PropertyEnum::String("4".to_string()));
+            }
+            TmxEntry::ImageLayer(_) | TmxEntry::Group(_) => {}
+        }
+    }
+
+    #[test]
+    fn resolve_tile_in_tileset() {
+        let tmx_content = TmxContent::load(asset_path("maps/town.tmx")).unwrap();
+
+        let (tileset, local_id, rect) = tmx_content.resolve_tile(&TileRef::from(1360)).unwrap();
+        assert_eq!(&*tileset.name, "Floor");
+        assert_eq!(local_id, 155);
+        assert_eq!(rect, Area::new(prelude::r32(128.0), prelude::r32(112.0), prelude::r32(16.0), prelude::r32(16.0)));
+
+        let (tileset, local_id, _) = tmx_content.resolve_tile(&TileRef::from(185)).unwrap();
+        assert_eq!(&*tileset.name, "Wall");
+        assert_eq!(local_id, 0);
+
+        assert!(tmx_content.resolve_tile(&TileRef::from(0)).is_none());
+        assert!(tmx_content.resolve_tile(&TileRef::from(5000)).is_none());
+    }
+
+    #[test]
+    fn keep_document_order() {
+        let tmx_content = TmxContent::load(asset_path("maps/town.tmx")).unwrap();
+
+        let layers: Vec<&str> = tmx_content.layers().map(|layer| layer.name.as_ref()).collect();
+        assert_eq!(layers, vec!["Background_Layer", "Ground_Layer", "Decoration_Layer"]);
+
+        let tilesets: Vec<&str> = tmx_content.tilesets().map(|tileset| tileset.name.as_ref()).collect();
+        assert_eq!(tilesets.len(), 13);
+        assert_eq!(tilesets[0], "Decor0");
+        assert_eq!(tilesets[12], "Container0");
+
+        let object_groups: Vec<&str> = tmx_content.object_groups().map(|group| group.name.as_ref()).collect();
+        assert_eq!(object_groups, vec![
+            "MAP_QUEST_ITEM_SPAWN_LAYER",
+            "MAP_COLLISION_LAYER",
+            "MAP_SPAWNS_LAYER",
+            "MAP_PORTAL_LAYER",
+            "PARTICLE_EFFECT_SPAWN_LAYER",
+        ]);
+        assert!(tmx_content.get_layer("MAP_COLLISION_LAYER").is_none());
+        assert!(tmx_content.get_object_group("MAP_COLLISION_LAYER").is_some());
+    }
+
+    #[test]
+    fn keep_duplicate_names() {
+        let xml = map(r#" <layer name="Ground" width="2" height="2">
+  <data encoding="csv">1,1,1,1</data>
+ </layer>
+ <objectgroup name="Ground"/>
+ <layer name="Ground" width="2" height="2">
+  <data encoding="csv">2,2,2,2</data>
+ </layer>"#);
+        let tmx_content = parse_tmx(xml.as_bytes()).unwrap();
+
+        assert_eq!(tmx_content.entries().len(), 3);
+        assert_eq!(tmx_content.get_all("Ground").count(), 3);
+        assert_eq!(tmx_content.get_layer("Ground").unwrap().grid[0][0].gid, 1);
+        assert!(tmx_content.get_object_group("Ground").is_some());
+
+        let gids: Vec<usize> = tmx_content.layers().map(|layer| layer.grid[1][1].gid).collect();
+        assert_eq!(gids, vec![1, 2]);
+        assert_eq!(tmx_content.get_all("Sky").count(), 0);
+    }
+
+    #[test]
+    fn load_missing_file() {
+        match TmxContent::load(asset_path("maps/does_not_exist.tmx")) {
+            Err(TmxError::Io(_)) => {}
+            other => panic!("expected io error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_invalid_xml() {
+        match parse_tmx(b"<map><layer></map>") {
+            Err(TmxError::Xml(_)) => {}
+            other => panic!("expected xml error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_missing_attribute() {
+        let xml = map(r#" <layer name="Ground" width="2">
+  <data encoding="csv">1,2,3,4</data>
+ </layer>"#);
+        match parse_tmx(xml.as_bytes()) {
+            Err(TmxError::MissingAttribute { ref element, ref attribute, position }) => {
+                assert_eq!(element, "layer");
+                assert_eq!(attribute, "height");
+                assert_eq!(position.row, 2);
+            }
+            other => panic!("expected missing attribute, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_invalid_number() {
+        let xml = map(r#" <objectgroup name="MAP_SPAWNS_LAYER">
+  <object id="1" name="START" x="a lot" y="16"/>
+ </objectgroup>"#);
+        match parse_tmx(xml.as_bytes()) {
+            Err(TmxError::InvalidNumber { ref element, ref attribute, ref value, .. }) => {
+                assert_eq!(element, "object");
+                assert_eq!(attribute, "x");
+                assert_eq!(value, "a lot");
+            }
+            other => panic!("expected invalid number, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_unknown_property_type() {
+        let xml = map(r#" <objectgroup name="MAP_SPAWNS_LAYER">
+  <object id="1" name="START" x="16" y="16">
+   <properties>
+    <property name="speed" type="vector" value="1,2"/>
+   </properties>
+  </object>
+ </objectgroup>"#);
+        match parse_tmx(xml.as_bytes()) {
+            Err(TmxError::UnknownPropertyType { ref property, ref type_, .. }) => {
+                assert_eq!(property, "speed");
+                assert_eq!(type_, "vector");
+            }
+            other => panic!("expected unknown property type, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_malformed_csv() {
+        let xml = map(r#" <layer name="Ground" width="2" height="2">
+  <data encoding="csv">1,2,3</data>
+ </layer>"#);
+        match parse_tmx(xml.as_bytes()) {
+            Err(TmxError::CellCount { ref element, expected, found, .. }) => {
+                assert_eq!(element, "data");
+                assert_eq!((expected, found), (4, 3));
             }
+            other => panic!("expected a cell count error, got {:?}", other),
+        }
+
+        let xml = map(r#" <layer name="Ground" width="2" height="2">
+  <data encoding="csv">1,2,x,4</data>
+ </layer>"#);
+        match parse_tmx(xml.as_bytes()) {
+            Err(TmxError::MalformedCsv { .. }) => {}
+            other => panic!("expected malformed csv, got {:?}", other),
         }
     }
 
-}
\ No newline at end of file
+}
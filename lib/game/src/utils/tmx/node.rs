@@ -1,26 +1,95 @@
-
 extern crate xml;
 
+use ::noisy_float::prelude::*;
+
 use std::io::{Read};
+use std::str::FromStr;
+use self::xml::common::{Position, TextPosition};
 use self::xml::reader::{XmlEvent, EventReader};
 use std::collections::HashMap;
 
+use super::error::TmxError;
+
 #[derive(Debug)]
 pub struct Node {
     pub name: String,
     pub attributes: HashMap<String, String>,
     pub children: Vec<Node>,
     pub content: String,
+    pub position: TextPosition,
 }
 
-pub fn read_node_elements<R: Read>(current: &mut Node, reader: &mut EventReader<R>) {
+impl Node {
+
+    pub fn child(&self, name: &str) -> Result<&Node, TmxError> {
+        self.children.iter()
+            .find(|child| child.name == name)
+            .ok_or_else(|| self.missing_element(name))
+    }
+
+    pub fn missing_element(&self, child: &str) -> TmxError {
+        TmxError::MissingElement {
+            element: self.name.clone(),
+            child: child.to_string(),
+            position: self.position,
+        }
+    }
+
+    pub fn parse<T: FromStr>(&self, attribute: &str, value: &str) -> Result<T, TmxError> {
+        value.trim().parse::<T>().map_err(|_| TmxError::InvalidNumber {
+            element: self.name.clone(),
+            attribute: attribute.to_string(),
+            value: value.to_string(),
+            position: self.position,
+        })
+    }
+
+    pub fn parse_r32(&self, attribute: &str, value: &str) -> Result<R32, TmxError> {
+        let number = self.parse::<f32>(attribute, value)?;
+        R32::try_new(number).ok_or_else(|| TmxError::InvalidNumber {
+            element: self.name.clone(),
+            attribute: attribute.to_string(),
+            value: value.to_string(),
+            position: self.position,
+        })
+    }
+
+    /// Parses a local tile id; Tiled writes -1 for "no tile".
+    pub fn parse_local_tile(&self, attribute: &str, value: &str) -> Result<Option<usize>, TmxError> {
+        let tile = self.parse::<i64>(attribute, value)?;
+        if tile < 0 {
+            Ok(None)
+        } else {
+            Ok(Some(tile as usize))
+        }
+    }
+
+    pub fn invalid_value(&self, attribute: &str, value: &str) -> TmxError {
+        TmxError::InvalidValue {
+            element: self.name.clone(),
+            attribute: attribute.to_string(),
+            value: value.to_string(),
+            position: self.position,
+        }
+    }
+
+    pub fn missing_attribute(&self, attribute: &str) -> TmxError {
+        TmxError::MissingAttribute {
+            element: self.name.clone(),
+            attribute: attribute.to_string(),
+            position: self.position,
+        }
+    }
+
+}
+
+pub fn read_node_elements<R: Read>(current: &mut Node, reader: &mut EventReader<R>) -> Result<(), TmxError> {
     loop {
-        let e = reader.next();
-        match e {
-            Ok(XmlEvent::EndDocument) | Err(_) => break,
-            Ok(XmlEvent::StartElement{ name, attributes, .. }) => {
+        match reader.next()? {
+            XmlEvent::EndDocument => break,
+            XmlEvent::StartElement{ name, attributes, .. } => {
                 let mut map = HashMap::new();
-                for ref attr in attributes.iter() {
+                for attr in attributes.iter() {
                     map.insert(attr.name.local_name.clone(), attr.value.clone());
                 }
                 let mut node = Node{
@@ -28,16 +97,17 @@ pub fn read_node_elements<R: Read>(current: &mut Node, reader: &mut EventReader<
                     attributes: map,
                     children: Vec::new(),
                     content: "".to_string(),
+                    position: reader.position(),
                 };
-                read_node_elements(&mut node, reader);
+                read_node_elements(&mut node, reader)?;
                 current.children.push(node);
             }
-            Ok(XmlEvent::Characters(ref data)) => {
+            XmlEvent::Characters(ref data) => {
                 current.content.push_str(data);
             }
-            Ok(XmlEvent::EndElement{..}) => break,
+            XmlEvent::EndElement{..} => break,
             _ => {}
         }
     }
+    Ok(())
 }
-
@@ -2,101 +2,375 @@
 use ::noisy_float::prelude::*;
 
 use std::collections::HashMap;
-use std::rc::Rc;
+use std::convert::TryFrom;
+use std::sync::Arc;
 
 use ::utils::tmx::property::*;
-use ::world::Area;
+use ::world::{Area, Point};
 
+use super::color::Color;
+use super::common::LayerCommon;
+use super::error::TmxError;
+use super::layer::TileRef;
 use super::node::Node;
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct TmxObjectGroup {
-    pub name: Rc<String>,
+    pub name: Arc<str>,
+    pub common: LayerCommon,
     pub objects: HashMap<usize, Object>,
+    pub properties: HashMap<Arc<str>, Property>,
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct Object {
     pub id: usize,
     pub name: String,
+    /// `type` in maps saved before Tiled 1.9, `class` after.
+    pub class: String,
+    pub class_attribute: ClassAttribute,
     pub area: Area,
-    pub properties: HashMap<Rc<String>, Property>,
+    pub shape: ObjectShape,
+    /// Clockwise rotation in degrees around the object's origin.
+    pub rotation: R32,
+    pub visible: bool,
+    pub gid: Option<TileRef>,
+    pub properties: HashMap<Arc<str>, Property>,
 }
 
-impl<'a> From<&'a Node> for TmxObjectGroup {
-    fn from(node: &'a Node) -> TmxObjectGroup {
+/// The attribute an object's class was read from, so that it is written back the same way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClassAttribute {
+    Type,
+    Class,
+}
 
-        let mut name = String::new();
-        let mut objects = HashMap::new();
+/// The geometry of an object. Polygon and polyline points are relative to
+/// the object's `x`/`y`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ObjectShape {
+    Rectangle,
+    Ellipse,
+    Point,
+    Polygon(Vec<Point>),
+    Polyline(Vec<Point>),
+    Text(TmxText),
+    /// A tile object, drawn with the tile in `Object::gid`.
+    Tile,
+}
 
-        for (ref key, ref value) in &node.attributes {
-            match key.as_ref() {
-                "name" => {
-                    name.clear();
-                    name.push_str(value.clone());
-                }
-                _ => {}
-            }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HAlign {
+    Left,
+    Center,
+    Right,
+    Justify,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VAlign {
+    Top,
+    Center,
+    Bottom,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TmxText {
+    pub text: String,
+    pub font_family: String,
+    pub pixel_size: usize,
+    pub wrap: bool,
+    pub color: Color,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub strikeout: bool,
+    pub halign: HAlign,
+    pub valign: VAlign,
+}
+
+impl<'a> TryFrom<&'a Node> for TmxObjectGroup {
+    type Error = TmxError;
+
+    fn try_from(node: &'a Node) -> Result<TmxObjectGroup, TmxError> {
+        let mut builder = ObjectGroupBuilder::new(node);
+        for child in node.children.iter() {
+            builder.child(child)?;
+        }
+        builder.finish(node)
+    }
+}
+
+/// Builds a `TmxObjectGroup` one child element at a time, like `LayerBuilder`.
+pub struct ObjectGroupBuilder {
+    name: String,
+    objects: HashMap<usize, Object>,
+    properties: HashMap<Arc<str>, Property>,
+}
+
+impl ObjectGroupBuilder {
+
+    pub fn new(node: &Node) -> ObjectGroupBuilder {
+        ObjectGroupBuilder {
+            name: node.attributes.get("name").cloned().unwrap_or_default(),
+            objects: HashMap::new(),
+            properties: HashMap::new(),
         }
+    }
 
-        for ref node in node.children.iter() {
-            match node.name.as_ref() {
-                "object" => {
-                    let o = Object::from(*node);
-                    objects.insert(o.id, o);
-                }
-                _ => {}
+    pub fn child(&mut self, child: &Node) -> Result<(), TmxError> {
+        match child.name.as_ref() {
+            "object" => {
+                let o = Object::try_from(child)?;
+                self.objects.insert(o.id, o);
             }
+            "properties" => {
+                Property::read_properties(child, &mut self.properties)?;
+            }
+            _ => {}
         }
+        Ok(())
+    }
 
-        TmxObjectGroup {
-            name: Rc::new(name),
-            objects,
-        }
+    pub fn finish(self, node: &Node) -> Result<TmxObjectGroup, TmxError> {
+        Ok(TmxObjectGroup {
+            name: Arc::from(self.name),
+            common: LayerCommon::from_node(node)?,
+            objects: self.objects,
+            properties: self.properties,
+        })
     }
+
 }
 
-impl<'a> From<&'a Node> for Object {
-    fn from(node: &'a Node) -> Object {
+impl<'a> TryFrom<&'a Node> for Object {
+    type Error = TmxError;
+
+    fn try_from(node: &'a Node) -> Result<Object, TmxError> {
         let mut id = 0;
         let mut name = String::new();
+        let mut class = String::new();
+        let mut class_attribute = ClassAttribute::Type;
         let mut x = r32(0.0);
         let mut y = r32(0.0);
-        let mut width = r32(0.0);
-        let mut height = r32(0.0);
+        let mut width = None;
+        let mut height = None;
+        let mut rotation = r32(0.0);
+        let mut visible = true;
+        let mut gid = None;
 
-        for (ref key, ref value) in &node.attributes {
+        for (key, value) in &node.attributes {
             match key.as_ref() {
                 "id" => {
-                    id = value.parse::<usize>().unwrap();
+                    id = node.parse::<usize>(key, value)?;
                 }
                 "name" => {
                     name.clear();
-                    name.push_str(value.clone());
+                    name.push_str(value);
+                }
+                "type" | "class" => {
+                    class.clear();
+                    class.push_str(value);
+                    class_attribute = if key == "class" { ClassAttribute::Class } else { ClassAttribute::Type };
                 }
                 "x" => {
-                    x = r32(value.parse::<f32>().unwrap());
+                    x = node.parse_r32(key, value)?;
                 }
                 "y" => {
-                    y = r32(value.parse::<f32>().unwrap());
+                    y = node.parse_r32(key, value)?;
                 }
                 "width" => {
-                    width = r32(value.parse::<f32>().unwrap());
+                    width = Some(node.parse_r32(key, value)?);
                 }
                 "height" => {
-                    height = r32(value.parse::<f32>().unwrap());
+                    height = Some(node.parse_r32(key, value)?);
+                }
+                "rotation" => {
+                    rotation = node.parse_r32(key, value)?;
+                }
+                "visible" => {
+                    visible = node.parse::<u8>(key, value)? != 0;
+                }
+                "gid" => {
+                    gid = Some(TileRef::from(node.parse::<u32>(key, value)?));
                 }
                 _ => {}
             }
         }
 
-        let properties = Property::properties_from_node(node);
+        let mut shape = None;
+
+        for child in node.children.iter() {
+            match child.name.as_ref() {
+                "ellipse" => shape = Some(ObjectShape::Ellipse),
+                "point" => shape = Some(ObjectShape::Point),
+                "polygon" => shape = Some(ObjectShape::Polygon(parse_points(child)?)),
+                "polyline" => shape = Some(ObjectShape::Polyline(parse_points(child)?)),
+                "text" => shape = Some(ObjectShape::Text(parse_text(child)?)),
+                _ => {}
+            }
+        }
 
-        Object {
+        let shape = match shape {
+            Some(shape) => shape,
+            None if gid.is_some() => ObjectShape::Tile,
+            // objects placed without a size, like the NPC_START spawns, are points
+            None if width.is_none() && height.is_none() => ObjectShape::Point,
+            None => ObjectShape::Rectangle,
+        };
+
+        let properties = Property::properties_from_node(node)?;
+
+        Ok(Object {
             id,
             name,
-            area: Area::new(x, y, width, height),
+            class,
+            class_attribute,
+            area: Area::new(x, y, width.unwrap_or_else(|| r32(0.0)), height.unwrap_or_else(|| r32(0.0))),
+            shape,
+            rotation,
+            visible,
+            gid,
             properties,
+        })
+    }
+}
+
+fn parse_points(node: &Node) -> Result<Vec<Point>, TmxError> {
+    let points = node.attributes.get("points").ok_or_else(|| node.missing_attribute("points"))?;
+
+    points.split_whitespace()
+        .map(|point| {
+            let mut coords = point.split(',');
+            match (coords.next(), coords.next(), coords.next()) {
+                (Some(x), Some(y), None) => Ok(Point::new(node.parse_r32("points", x)?, node.parse_r32("points", y)?)),
+                _ => Err(node.invalid_value("points", points)),
+            }
+        })
+        .collect()
+}
+
+fn parse_text(node: &Node) -> Result<TmxText, TmxError> {
+    let mut text = TmxText {
+        text: node.content.clone(),
+        font_family: "sans-serif".to_string(),
+        pixel_size: 16,
+        wrap: false,
+        color: Color::new(0, 0, 0, 255),
+        bold: false,
+        italic: false,
+        underline: false,
+        strikeout: false,
+        halign: HAlign::Left,
+        valign: VAlign::Top,
+    };
+
+    for (key, value) in &node.attributes {
+        match key.as_ref() {
+            "fontfamily" => text.font_family = value.clone(),
+            "pixelsize" => text.pixel_size = node.parse::<usize>(key, value)?,
+            "wrap" => text.wrap = node.parse::<u8>(key, value)? != 0,
+            "color" => text.color = Color::from_node(node, key, value)?,
+            "bold" => text.bold = node.parse::<u8>(key, value)? != 0,
+            "italic" => text.italic = node.parse::<u8>(key, value)? != 0,
+            "underline" => text.underline = node.parse::<u8>(key, value)? != 0,
+            "strikeout" => text.strikeout = node.parse::<u8>(key, value)? != 0,
+            "halign" => {
+                text.halign = match value.as_ref() {
+                    "left" => HAlign::Left,
+                    "center" => HAlign::Center,
+                    "right" => HAlign::Right,
+                    "justify" => HAlign::Justify,
+                    _ => return Err(node.invalid_value(key, value)),
+                };
+            }
+            "valign" => {
+                text.valign = match value.as_ref() {
+                    "top" => VAlign::Top,
+                    "center" => VAlign::Center,
+                    "bottom" => VAlign::Bottom,
+                    _ => return Err(node.invalid_value(key, value)),
+                };
+            }
+            _ => {}
         }
     }
-}
\ No newline at end of file
+
+    Ok(text)
+}
+
+#[cfg(test)]
+mod test {
+
+    use ::noisy_float::prelude::*;
+    use super::*;
+    use super::super::fixtures::{map, parse_tmx};
+
+    #[test]
+    fn read_object_shapes() {
+        let xml = map(r##" <objectgroup name="Objects">
+  <object id="1" name="wall" type="Solid" x="8" y="16" width="32" height="8" rotation="45"/>
+  <object id="2" x="0" y="0" width="16" height="24">
+   <ellipse/>
+  </object>
+  <object id="3" name="START" x="64" y="64">
+   <point/>
+  </object>
+  <object id="4" x="10" y="20">
+   <polygon points="0,0 16,0 8,-12.5"/>
+  </object>
+  <object id="5" class="Path" x="0" y="0" visible="0">
+   <polyline points="0,0 32,32"/>
+  </object>
+  <object id="6" x="0" y="0" width="64" height="16">
+   <text fontfamily="Serif" pixelsize="12" wrap="1" color="#ff0000" halign="center">Welcome to town</text>
+  </object>
+  <object id="7" gid="2147483811" x="48" y="64" width="16" height="16"/>
+  <object id="8" name="NPC_START" x="88" y="232"/>
+ </objectgroup>"##);
+        let tmx_content = parse_tmx(xml.as_bytes()).unwrap();
+        let objects = &tmx_content.get_object_group("Objects").unwrap().objects;
+
+        let wall = objects.get(&1).unwrap();
+        assert_eq!(wall.shape, ObjectShape::Rectangle);
+        assert_eq!(wall.class, "Solid");
+        assert_eq!(wall.class_attribute, ClassAttribute::Type);
+        assert_eq!(wall.rotation, r32(45.0));
+        assert!(wall.visible);
+        assert_eq!(wall.area, Area::new(r32(8.0), r32(16.0), r32(32.0), r32(8.0)));
+
+        assert_eq!(objects.get(&2).unwrap().shape, ObjectShape::Ellipse);
+        assert_eq!(objects.get(&3).unwrap().shape, ObjectShape::Point);
+        assert_eq!(objects.get(&4).unwrap().shape, ObjectShape::Polygon(vec![
+            Point::new(r32(0.0), r32(0.0)), Point::new(r32(16.0), r32(0.0)), Point::new(r32(8.0), r32(-12.5)),
+        ]));
+
+        let path = objects.get(&5).unwrap();
+        assert_eq!(path.shape, ObjectShape::Polyline(vec![Point::new(r32(0.0), r32(0.0)), Point::new(r32(32.0), r32(32.0))]));
+        assert_eq!(path.class, "Path");
+        assert_eq!(path.class_attribute, ClassAttribute::Class);
+        assert!(!path.visible);
+
+        match objects.get(&6).unwrap().shape {
+            ObjectShape::Text(ref text) => {
+                assert_eq!(text.text, "Welcome to town");
+                assert_eq!(text.font_family, "Serif");
+                assert_eq!(text.pixel_size, 12);
+                assert!(text.wrap);
+                assert_eq!(text.color, Color::new(255, 0, 0, 255));
+                assert_eq!(text.halign, HAlign::Center);
+                assert_eq!(text.valign, VAlign::Top);
+            }
+            ref other => panic!("expected text, got {:?}", other),
+        }
+
+        let tile = objects.get(&7).unwrap();
+        assert_eq!(tile.shape, ObjectShape::Tile);
+        assert_eq!(tile.gid, Some(TileRef { gid: 163, flip_h: true, ..TileRef::default() }));
+
+        let npc = objects.get(&8).unwrap();
+        assert_eq!(npc.shape, ObjectShape::Point);
+        assert_eq!(npc.gid, None);
+    }
+
+}
@@ -1,14 +1,16 @@
-
 use ::noisy_float::prelude::*;
 
 use std::collections::HashMap;
-use std::rc::Rc;
+use std::path::Path;
+use std::sync::Arc;
 
+use super::color::Color;
+use super::error::TmxError;
 use super::node::Node;
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct Property {
-    pub name: Rc<String>,
+    pub name: Arc<str>,
     pub value: PropertyEnum,
 }
 
@@ -16,56 +18,225 @@ pub struct Property {
 pub enum PropertyEnum {
     Float(R32),
     String(String),
+    Int(i64),
+    Bool(bool),
+    /// `None` when the color was left unset in Tiled.
+    Color(Option<Color>),
+    /// A path as written in the map, relative to the map file.
+    File(String),
+    /// The id of the referenced object, 0 when unset.
+    Object(usize),
+    Class {
+        propertytype: String,
+        properties: HashMap<Arc<str>, Property>,
+    },
+}
+
+impl PropertyEnum {
+
+    pub fn as_float(&self) -> Option<R32> {
+        match *self {
+            PropertyEnum::Float(f) => Some(f),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            PropertyEnum::String(ref s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        match *self {
+            PropertyEnum::Int(i) => Some(i),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            PropertyEnum::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn as_color(&self) -> Option<Color> {
+        match *self {
+            PropertyEnum::Color(color) => color,
+            _ => None,
+        }
+    }
+
+    pub fn as_path(&self) -> Option<&Path> {
+        match *self {
+            PropertyEnum::File(ref file) => Some(Path::new(file)),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<usize> {
+        match *self {
+            PropertyEnum::Object(id) if id != 0 => Some(id),
+            _ => None,
+        }
+    }
+
+    pub fn as_class(&self) -> Option<&HashMap<Arc<str>, Property>> {
+        match *self {
+            PropertyEnum::Class { ref properties, .. } => Some(properties),
+            _ => None,
+        }
+    }
+
 }
 
 impl Property {
-    pub fn properties_from_node(node: &Node) -> HashMap<Rc<String>, Property> {
+    pub fn properties_from_node(node: &Node) -> Result<HashMap<Arc<str>, Property>, TmxError> {
 
         let mut properties = HashMap::new();
 
         for node in node.children.iter() {
-            match node.name.as_ref() {
-                "properties" => {
-                    for node in node.children.iter() {
-                        match node.name.as_ref() {
-                            "property" => {
-                                let mut name = String::new();
-                                let mut type_ = String::from("string");
-                                let mut value = String::new();
-
-                                for (ref k, ref v) in &node.attributes {
-                                    match k.as_ref() {
-                                        "name" => {
-                                            name.push_str(v.clone());
-                                        }
-                                        "type" => {
-                                            type_.clear();
-                                            type_.push_str(v.clone());
-                                        }
-                                        "value" => {
-                                            value.push_str(v.clone());
-                                        }
-                                        _ => {}
-                                    }
-                                }
-                                let property = Property {
-                                    name: Rc::new(name),
-                                    value: match type_.as_ref() {
-                                        "string" => PropertyEnum::String(value),
-                                        "float" => PropertyEnum::Float(r32(value.parse::<f32>().unwrap())),
-                                        _ => panic!("no property type match"),
-                                    }
-                                };
-                                properties.insert(property.name.clone(), property);
-                            }
-                            _ => {}
-                        }
+            if node.name == "properties" {
+                Property::read_properties(node, &mut properties)?;
+            }
+        }
+
+        Ok(properties)
+    }
+
+    /// Adds the `<property>` children of a `<properties>` element to `properties`.
+    pub fn read_properties(node: &Node, properties: &mut HashMap<Arc<str>, Property>) -> Result<(), TmxError> {
+        for node in node.children.iter() {
+            if node.name != "property" {
+                continue;
+            }
+            let mut name = None;
+            let mut type_ = String::from("string");
+            let mut propertytype = String::new();
+            let mut value = None;
+
+            for (k, v) in &node.attributes {
+                match k.as_ref() {
+                    "name" => {
+                        name = Some(v.clone());
+                    }
+                    "type" => {
+                        type_.clear();
+                        type_.push_str(v);
                     }
+                    "propertytype" => {
+                        propertytype.push_str(v);
+                    }
+                    "value" => {
+                        value = Some(v.clone());
+                    }
+                    _ => {}
                 }
-                _ => {}
             }
+            let name = name.ok_or_else(|| node.missing_attribute("name"))?;
+            // multi-line strings are written as element text instead of an attribute
+            let value = value.unwrap_or_else(|| node.content.clone());
+            let value = match type_.as_ref() {
+                "string" => PropertyEnum::String(value),
+                "float" => PropertyEnum::Float(node.parse_r32("value", &value)?),
+                "int" => PropertyEnum::Int(node.parse::<i64>("value", &value)?),
+                "bool" => match value.as_ref() {
+                    "true" => PropertyEnum::Bool(true),
+                    "false" => PropertyEnum::Bool(false),
+                    _ => return Err(node.invalid_value("value", &value)),
+                },
+                "color" if value.is_empty() => PropertyEnum::Color(None),
+                "color" => PropertyEnum::Color(Some(Color::from_node(node, "value", &value)?)),
+                "file" => PropertyEnum::File(value),
+                "object" => PropertyEnum::Object(node.parse::<usize>("value", &value)?),
+                "class" => PropertyEnum::Class {
+                    propertytype,
+                    properties: Property::properties_from_node(node)?,
+                },
+                _ => return Err(TmxError::UnknownPropertyType {
+                    element: node.name.clone(),
+                    property: name,
+                    type_,
+                    position: node.position,
+                }),
+            };
+            let property = Property {
+                name: Arc::from(name),
+                value,
+            };
+            properties.insert(property.name.clone(), property);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use ::noisy_float::prelude::*;
+    use super::*;
+    use super::super::fixtures::{map, parse_tmx};
+
+    use std::path::Path;
+
+    #[test]
+    fn read_typed_properties() {
+        let xml = map(r##" <objectgroup name="MAP_QUEST_ITEM_SPAWN_LAYER">
+  <object id="1" name="1" x="16" y="16">
+   <properties>
+    <property name="taskID" type="int" value="-3"/>
+    <property name="Penalty" type="float" value="1.2"/>
+    <property name="respawns" type="bool" value="true"/>
+    <property name="glow" type="color" value="#80ff8000"/>
+    <property name="shadow" type="color" value=""/>
+    <property name="script" type="file" value="../scripts/quest003_task002.json"/>
+    <property name="target" type="object" value="12"/>
+    <property name="description">First line
+second line</property>
+    <property name="loot" type="class" propertytype="Loot">
+     <properties>
+      <property name="gold" type="int" value="25"/>
+     </properties>
+    </property>
+   </properties>
+  </object>
+ </objectgroup>"##);
+        let tmx_content = parse_tmx(xml.as_bytes()).unwrap();
+        let object = tmx_content.get_object_group("MAP_QUEST_ITEM_SPAWN_LAYER").unwrap().objects.get(&1).unwrap();
+        let property = |name: &str| &object.properties.get(name).unwrap().value;
+
+        assert_eq!(property("taskID").as_int(), Some(-3));
+        assert_eq!(property("Penalty").as_float(), Some(r32(1.2)));
+        assert_eq!(property("respawns").as_bool(), Some(true));
+        assert_eq!(property("glow").as_color(), Some(Color::new(255, 128, 0, 128)));
+        assert_eq!(property("shadow"), &PropertyEnum::Color(None));
+        assert_eq!(property("script").as_path(), Some(Path::new("../scripts/quest003_task002.json")));
+        assert_eq!(property("target").as_object(), Some(12));
+        assert_eq!(property("description").as_str(), Some("First line\nsecond line"));
+        assert_eq!(property("taskID").as_str(), None);
+
+        match *property("loot") {
+            PropertyEnum::Class { ref propertytype, ref properties } => {
+                assert_eq!(propertytype, "Loot");
+                assert_eq!(properties.get("gold").unwrap().value.as_int(), Some(25));
+            }
+            ref other => panic!("expected class property, got {:?}", other),
         }
 
-        properties
+        let xml = map(r#" <objectgroup name="Objects">
+  <object id="1" x="0" y="0">
+   <properties>
+    <property name="respawns" type="bool" value="yes"/>
+   </properties>
+  </object>
+ </objectgroup>"#);
+        match parse_tmx(xml.as_bytes()) {
+            Err(TmxError::InvalidValue { ref value, .. }) => assert_eq!(value, "yes"),
+            other => panic!("expected invalid value, got {:?}", other),
+        }
     }
-}
\ No newline at end of file
+
+}
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::{Component, Path, PathBuf};
+
+use super::error::TmxError;
+
+/// Where maps and the files they refer to are read from. A `TilesetCache`
+/// carries one, so everything loaded through that cache uses it.
+pub trait Resolver: fmt::Debug + Send + Sync {
+
+    /// Opens `path`, as returned by `resolve`.
+    fn open<'a>(&'a self, path: &Path) -> Result<Box<dyn Read + 'a>, TmxError>;
+
+    fn exists(&self, path: &Path) -> bool {
+        self.open(path).is_ok()
+    }
+
+    /// The file `reference` names when it is relative to `base_dir`, like the
+    /// `source` of an external tileset relative to the map's directory.
+    fn resolve(&self, base_dir: &Path, reference: &Path) -> PathBuf {
+        normalize(&base_dir.join(reference))
+    }
+
+}
+
+/// Reads from disk. Resolved paths are canonical where the file exists.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileResolver;
+
+impl Resolver for FileResolver {
+
+    fn open<'a>(&'a self, path: &Path) -> Result<Box<dyn Read + 'a>, TmxError> {
+        Ok(Box::new(BufReader::new(File::open(path)?)))
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    fn resolve(&self, base_dir: &Path, reference: &Path) -> PathBuf {
+        let path = base_dir.join(reference);
+        path.canonicalize().unwrap_or_else(|_| normalize(&path))
+    }
+
+}
+
+/// Serves files from memory, e.g. unpacked from an asset pack or set up by a test.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryResolver {
+    files: HashMap<PathBuf, Vec<u8>>,
+}
+
+impl MemoryResolver {
+
+    pub fn new() -> MemoryResolver {
+        MemoryResolver::default()
+    }
+
+    /// Adds or replaces the file at `path`; `..` and `.` in it are resolved first.
+    pub fn insert<P: AsRef<Path>, B: Into<Vec<u8>>>(&mut self, path: P, contents: B) {
+        self.files.insert(normalize(path.as_ref()), contents.into());
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+}
+
+impl Resolver for MemoryResolver {
+
+    fn open<'a>(&'a self, path: &Path) -> Result<Box<dyn Read + 'a>, TmxError> {
+        match self.files.get(&normalize(path)) {
+            Some(contents) => Ok(Box::new(&contents[..])),
+            None => Err(TmxError::Io(io::Error::new(io::ErrorKind::NotFound, format!("'{}' is not in memory", path.display())))),
+        }
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.files.contains_key(&normalize(path))
+    }
+
+}
+
+/// Removes `.` and resolves `..` against the preceding component without
+/// touching the disk. A `..` that would leave a relative path is kept.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                Some(Component::RootDir) | Some(Component::Prefix(_)) => {}
+                _ => normalized.push(".."),
+            },
+            _ => normalized.push(component.as_os_str()),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use ::utils::asset_path;
+    use super::super::{TilesetCache, TmxContent, load_map_with_cache};
+    use super::super::fixtures::map;
+
+    use std::fs;
+
+    #[test]
+    fn normalize_paths() {
+        assert_eq!(normalize(Path::new("maps/../sprites/./objects/Wall.png")), PathBuf::from("sprites/objects/Wall.png"));
+        assert_eq!(normalize(Path::new("maps/../../sprites/Wall.png")), PathBuf::from("../sprites/Wall.png"));
+        assert_eq!(normalize(Path::new("/maps/../../Wall.png")), PathBuf::from("/Wall.png"));
+        assert_eq!(normalize(Path::new("./town.tmx")), PathBuf::from("town.tmx"));
+    }
+
+    #[test]
+    fn memory_resolver_reads_inserted_files() {
+        let mut files = MemoryResolver::new();
+        files.insert("maps/./town.tmx", "<map/>");
+
+        let path = files.resolve(Path::new("maps/tilesets"), Path::new("../town.tmx"));
+        assert_eq!(path, PathBuf::from("maps/town.tmx"));
+
+        let mut contents = String::new();
+        files.open(&path).unwrap().read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "<map/>");
+
+        match files.open(Path::new("maps/castle.tmx")) {
+            Err(TmxError::Io(ref err)) => assert_eq!(err.kind(), io::ErrorKind::NotFound),
+            _ => panic!("expected a missing file"),
+        };
+    }
+
+    #[test]
+    fn load_maps_from_memory() {
+        let mut files = MemoryResolver::new();
+        files.insert("pack/tilesets/Wall.tsx", r#"<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.0" name="Wall" tilewidth="16" tileheight="16" tilecount="1020" columns="20">
+ <image source="../sprites/objects/Wall.png" width="320" height="816"/>
+</tileset>"#);
+        files.insert("pack/maps/castle.tmx", map(r#" <tileset firstgid="1" source="../tilesets/Wall.tsx"/>"#));
+        files.insert("pack/maps/town.tmj", fs::read(asset_path("maps/town.tmj")).unwrap());
+
+        let mut cache = TilesetCache::with_resolver(files);
+        let castle = load_map_with_cache("pack/maps/castle.tmx", &mut cache).unwrap();
+        assert_eq!(castle.get_tileset("Wall").unwrap().columns, 20);
+        assert!(cache.contains(Path::new("pack/tilesets/Wall.tsx")));
+
+        let town = load_map_with_cache("pack/maps/town.tmj", &mut cache).unwrap();
+        assert_eq!(town, TmxContent::load(asset_path("maps/town.tmx")).unwrap().with_base_dir("pack/maps"));
+
+        let buffer = fs::read(asset_path("maps/town.tmx")).unwrap();
+        assert_eq!(super::super::parse_tmx(&buffer[..], Path::new("pack/maps"), &mut cache).unwrap(), town);
+
+        match load_map_with_cache("pack/maps/town.tmx", &mut cache) {
+            Err(TmxError::Io(_)) => {}
+            other => panic!("expected a missing file, got {:?}", other),
+        }
+    }
+
+}
@@ -0,0 +1,220 @@
+extern crate xml;
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io::Read;
+use std::path::Path;
+
+use self::xml::common::{Position, TextPosition};
+use self::xml::reader::{EventReader, XmlEvent};
+
+use super::{TmxContent, TmxEntry};
+use super::error::TmxError;
+use super::group::TmxGroup;
+use super::imagelayer::TmxImageLayer;
+use super::layer::LayerBuilder;
+use super::map::TmxMap;
+use super::node::{Node, read_node_elements};
+use super::objectgroup::ObjectGroupBuilder;
+use super::tileset::TilesetBuilder;
+use super::tsx::TilesetCache;
+
+/// Reads a `.tmx` document in a single pass. Layers, object groups and
+/// tilesets are built while their children stream by, so at most one child
+/// element (a `<data>`, an `<object>`, a `<tile>`) is held as a `Node`.
+pub fn read_map<R: Read>(source: R, base_dir: &Path, cache: &mut TilesetCache) -> Result<TmxContent, TmxError> {
+    let mut reader = ElementReader {
+        events: super::parser_config().create_reader(source),
+    };
+
+    let mut map = loop {
+        match reader.next_start()? {
+            Some(node) => {
+                if node.name == "map" {
+                    break node;
+                }
+                reader.skip()?;
+            }
+            None => return Err(shell("root", TextPosition::new()).missing_element("map")),
+        }
+    };
+
+    let entries = read_entries(&mut reader, &mut map, base_dir, cache)?;
+
+    Ok(TmxContent::new(TmxMap::from_node(&map)?, entries).with_base_dir(base_dir))
+}
+
+/// Reads the children of `parent` up to its end tag. `<properties>` are
+/// added to `parent`, everything else is turned into entries.
+fn read_entries<R: Read>(reader: &mut ElementReader<R>, parent: &mut Node, base_dir: &Path, cache: &mut TilesetCache) -> Result<Vec<TmxEntry>, TmxError> {
+
+    let mut entries = Vec::new();
+
+    while let Some(mut node) = reader.next_start()? {
+        match node.name.as_ref() {
+            "layer" => {
+                let mut builder = LayerBuilder::new(&node)?;
+                while let Some(child) = reader.next_child()? {
+                    builder.child(&child)?;
+                }
+                entries.push(TmxEntry::Layer(builder.finish(&node)?));
+            }
+            "tileset" => {
+                let tileset = match node.attributes.get("source") {
+                    Some(source) => {
+                        reader.skip()?;
+                        let firstgid = node.attributes.get("firstgid").ok_or_else(|| node.missing_attribute("firstgid"))?;
+                        cache.load(base_dir, source, node.parse::<usize>("firstgid", firstgid)?)?
+                    }
+                    None => {
+                        let firstgid = match node.attributes.get("firstgid") {
+                            Some(value) => node.parse::<usize>("firstgid", value)?,
+                            None => 0,
+                        };
+                        let mut builder = TilesetBuilder::new(&node, firstgid, None)?;
+                        while let Some(child) = reader.next_child()? {
+                            builder.child(&child)?;
+                        }
+                        builder.finish()
+                    }
+                };
+                entries.push(TmxEntry::Tileset(tileset));
+            }
+            "objectgroup" => {
+                let mut builder = ObjectGroupBuilder::new(&node);
+                while let Some(child) = reader.next_child()? {
+                    builder.child(&child)?;
+                }
+                entries.push(TmxEntry::ObjectGroup(builder.finish(&node)?));
+            }
+            "imagelayer" => {
+                reader.read_rest(&mut node)?;
+                entries.push(TmxEntry::ImageLayer(TmxImageLayer::try_from(&node)?));
+            }
+            "group" => {
+                let children = read_entries(reader, &mut node, base_dir, cache)?;
+                entries.push(TmxEntry::Group(TmxGroup::from_node(&node, children)?));
+            }
+            "properties" => {
+                reader.read_rest(&mut node)?;
+                parent.children.push(node);
+            }
+            _ => {
+                reader.skip()?;
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+struct ElementReader<R: Read> {
+    events: EventReader<R>,
+}
+
+impl<R: Read> ElementReader<R> {
+
+    /// The next child of the current element with its attributes only, or
+    /// `None` once the current element has ended.
+    fn next_start(&mut self) -> Result<Option<Node>, TmxError> {
+        loop {
+            match self.events.next()? {
+                XmlEvent::StartElement { name, attributes, .. } => {
+                    let mut node = shell(&name.local_name, self.events.position());
+                    for attr in attributes {
+                        node.attributes.insert(attr.name.local_name, attr.value);
+                    }
+                    return Ok(Some(node));
+                }
+                XmlEvent::EndElement { .. } | XmlEvent::EndDocument => return Ok(None),
+                _ => {}
+            }
+        }
+    }
+
+    /// The next child of the current element, read completely.
+    fn next_child(&mut self) -> Result<Option<Node>, TmxError> {
+        match self.next_start()? {
+            Some(mut node) => {
+                self.read_rest(&mut node)?;
+                Ok(Some(node))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Reads the children and text of an element `next_start` returned.
+    fn read_rest(&mut self, node: &mut Node) -> Result<(), TmxError> {
+        read_node_elements(node, &mut self.events)
+    }
+
+    /// Skips the rest of an element `next_start` returned.
+    fn skip(&mut self) -> Result<(), TmxError> {
+        let mut depth = 0;
+        loop {
+            match self.events.next()? {
+                XmlEvent::StartElement { .. } => depth += 1,
+                XmlEvent::EndElement { .. } if depth == 0 => return Ok(()),
+                XmlEvent::EndElement { .. } => depth -= 1,
+                XmlEvent::EndDocument => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+
+}
+
+fn shell(name: &str, position: TextPosition) -> Node {
+    Node {
+        name: name.to_string(),
+        attributes: HashMap::new(),
+        children: Vec::new(),
+        content: String::new(),
+        position,
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use ::utils::shipped_maps;
+    use super::super::parse_tmx_document;
+
+    use std::fs;
+
+    #[test]
+    fn stream_matches_document_parser() {
+        for file_name in shipped_maps() {
+            let buffer = fs::read(&file_name).unwrap();
+            let base_dir = file_name.parent().unwrap();
+
+            let streamed = read_map(&buffer[..], base_dir, &mut TilesetCache::new()).unwrap();
+            let document = parse_tmx_document(&buffer[..], base_dir, &mut TilesetCache::new()).unwrap();
+            assert_eq!(streamed, document, "{} differs between parsers", file_name.display());
+        }
+    }
+
+    #[test]
+    fn stream_skips_unknown_elements() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.0" orientation="orthogonal" width="1" height="1" tilewidth="16" tileheight="16">
+ <editorsettings><export target="town.json" format="json"/></editorsettings>
+ <properties><property name="music" value="town.ogg"/></properties>
+ <group name="Town">
+  <properties><property name="zone" value="town"/></properties>
+  <layer name="Ground" width="1" height="1"><data encoding="csv">7</data></layer>
+ </group>
+</map>"#;
+        let content = read_map(xml.as_bytes(), Path::new("."), &mut TilesetCache::new()).unwrap();
+        assert_eq!(content.map.property("music").unwrap().as_str(), Some("town.ogg"));
+        assert_eq!(content.get_group("Town").unwrap().properties.len(), 1);
+        assert_eq!(content.get_layer("Ground").unwrap().grid[0][0].gid, 7);
+
+        match read_map(&b"<tileset/>"[..], Path::new("."), &mut TilesetCache::new()) {
+            Err(TmxError::MissingElement { ref element, ref child, .. }) => assert_eq!((element.as_str(), child.as_str()), ("root", "map")),
+            other => panic!("expected a missing map, got {:?}", other),
+        }
+    }
+
+}
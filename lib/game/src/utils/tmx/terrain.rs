@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::Node;
+use super::error::TmxError;
+use super::property::*;
+
+/// A legacy `<terrain>` type; tiles refer to it by index in `TmxTileset::terrain_types`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Terrain {
+    pub name: String,
+    /// Local id of the tile representing the terrain.
+    pub tile: Option<usize>,
+    pub properties: HashMap<Arc<str>, Property>,
+}
+
+impl Terrain {
+
+    pub fn from_node(node: &Node) -> Result<Terrain, TmxError> {
+        let tile = match node.attributes.get("tile") {
+            Some(tile) => node.parse_local_tile("tile", tile)?,
+            None => None,
+        };
+
+        Ok(Terrain {
+            name: node.attributes.get("name").cloned().unwrap_or_default(),
+            tile,
+            properties: Property::properties_from_node(node)?,
+        })
+    }
+
+}
+
+/// Parses a tile's `terrain` attribute: four comma separated terrain indices
+/// for the top left, top right, bottom left and bottom right corners.
+pub fn parse_terrain(node: &Node, value: &str) -> Result<[Option<usize>; 4], TmxError> {
+    let mut corners = [None; 4];
+    let parts: Vec<&str> = value.split(',').collect();
+
+    if parts.len() != 4 {
+        return Err(node.invalid_value("terrain", value));
+    }
+
+    for (corner, part) in corners.iter_mut().zip(parts) {
+        if !part.trim().is_empty() {
+            *corner = Some(node.parse::<usize>("terrain", part)?);
+        }
+    }
+
+    Ok(corners)
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::super::fixtures::{map, parse_tmx};
+
+    #[test]
+    fn read_terrain_types() {
+        let xml = map(r##" <tileset firstgid="1" name="Ground0" tilewidth="16" tileheight="16" tilecount="56" columns="8">
+  <image source="../sprites/objects/Ground0.png" width="128" height="112"/>
+  <terraintypes>
+   <terrain name="Grass" tile="9"/>
+   <terrain name="Dirt" tile="-1"/>
+  </terraintypes>
+  <tile id="0" terrain="0,0,,1"/>
+ </tileset>"##);
+        let tmx_content = parse_tmx(xml.as_bytes()).unwrap();
+        let ground = tmx_content.get_tileset("Ground0").unwrap();
+
+        assert_eq!(ground.terrain_types.len(), 2);
+        assert_eq!(ground.terrain_types[0].name, "Grass");
+        assert_eq!(ground.terrain_types[0].tile, Some(9));
+        assert_eq!(ground.terrain_types[1].tile, None);
+        assert_eq!(ground.tile(1).unwrap().terrain, Some([Some(0), Some(0), None, Some(1)]));
+    }
+
+}
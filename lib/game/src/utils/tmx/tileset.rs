@@ -1,142 +1,389 @@
-
-use ::noisy_float::*;
 use ::noisy_float::prelude::*;
 
 use super::Node;
+use super::animation::Animation;
+use super::error::TmxError;
+use super::image::TmxImage;
+use super::objectgroup::{Object, TmxObjectGroup};
 use super::property::*;
+use super::terrain::{Terrain, parse_terrain};
+use super::wang::WangSet;
 
 use std::collections::HashMap;
-use std::rc::Rc;
+use std::convert::TryFrom;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use ::world::Area;
+
+/// Where the tile of a tile object is anchored relative to the object position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectAlignment {
+    Unspecified,
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl ObjectAlignment {
 
+    pub fn from_node(node: &Node, attribute: &str, value: &str) -> Result<ObjectAlignment, TmxError> {
+        Ok(match value {
+            "unspecified" => ObjectAlignment::Unspecified,
+            "topleft" => ObjectAlignment::TopLeft,
+            "top" => ObjectAlignment::Top,
+            "topright" => ObjectAlignment::TopRight,
+            "left" => ObjectAlignment::Left,
+            "center" => ObjectAlignment::Center,
+            "right" => ObjectAlignment::Right,
+            "bottomleft" => ObjectAlignment::BottomLeft,
+            "bottom" => ObjectAlignment::Bottom,
+            "bottomright" => ObjectAlignment::BottomRight,
+            _ => return Err(node.invalid_value(attribute, value)),
+        })
+    }
+
+}
+
+/// A tileset as used by one map: the map's `firstgid` and `source` for it
+/// and the tileset itself, which maps loading the same `.tsx` share.
 #[derive(Debug, PartialEq, Eq)]
 pub struct TmxTileset {
-    pub name: Rc<String>,
+    pub firstgid: usize,
+    pub source: Option<String>,
+    pub data: Arc<TilesetData>,
+}
+
+impl Deref for TmxTileset {
+    type Target = TilesetData;
+
+    fn deref(&self) -> &TilesetData {
+        &self.data
+    }
+}
+
+impl TmxTileset {
+
+    pub fn tile(&self, gid: usize) -> Option<&Tile> {
+        self.local_id(gid).and_then(|id| self.tiles.get(&id))
+    }
+
+    pub fn property(&self, gid: usize, name: &str) -> Option<&PropertyEnum> {
+        if let Some(tile) = self.tile(gid) {
+            if let Some(property) = tile.properties.get(name) {
+                return Some(&property.value);
+            }
+        }
+
+        None
+    }
+
+    pub fn contains_gid(&self, gid: usize) -> bool {
+        gid >= self.firstgid && gid < self.firstgid + self.tilecount
+    }
+
+    /// The index of `gid` inside this tileset, counted from the top left of the image.
+    pub fn local_id(&self, gid: usize) -> Option<usize> {
+        if self.contains_gid(gid) {
+            Some(gid - self.firstgid)
+        } else {
+            None
+        }
+    }
+
+}
+
+/// The contents of a `<tileset>`; tiles are keyed by their local id.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TilesetData {
+    pub name: Arc<str>,
+    pub tilecount: usize,
     pub columns: usize,
     pub rows: usize,
     pub tilewidth: usize,
     pub tileheight: usize,
+    pub margin: usize,
+    pub spacing: usize,
+    pub class: String,
+    pub tile_offset: (i32, i32),
+    pub object_alignment: ObjectAlignment,
+    pub terrain_types: Vec<Terrain>,
+    pub wang_sets: Vec<WangSet>,
     pub tiles: HashMap<usize, Tile>,
     pub image_file_name: String,
+    pub image: Option<TmxImage>,
 }
 
-impl TmxTileset {
+impl TilesetData {
 
-    pub fn tile(&self, id: usize) -> Option<&Tile> {
-        self.tiles.get(&id)
+    pub fn get_wang_set(&self, name: &str) -> Option<&WangSet> {
+        self.wang_sets.iter().find(|wang_set| wang_set.name == name)
     }
 
-    pub fn property(&self, id: usize, name: &str) -> Option<&PropertyEnum> {
-        if let Some(tile) = self.tile(id) {
-            if let Some(property) = tile.properties.get(&Rc::new(name.to_string())) {
-                Some(&property.value);
-            }
+    /// The pixel rectangle of the tile with the given local id in the tileset image.
+    pub fn source_rect(&self, local_id: usize) -> Option<Area> {
+        if local_id >= self.tilecount || self.columns == 0 {
+            return None;
         }
+        let col = local_id % self.columns;
+        let row = local_id / self.columns;
+        let x = self.margin + col * (self.tilewidth + self.spacing);
+        let y = self.margin + row * (self.tileheight + self.spacing);
+        Some(Area::new(r32(x as f32), r32(y as f32), r32(self.tilewidth as f32), r32(self.tileheight as f32)))
+    }
 
-        None
+}
+
+impl<'a> TryFrom<&'a Node> for TmxTileset {
+    type Error = TmxError;
+
+    fn try_from(node: &'a Node) -> Result<TmxTileset, TmxError> {
+        let firstgid = match node.attributes.get("firstgid") {
+            Some(value) => node.parse::<usize>("firstgid", value)?,
+            None => 0,
+        };
+        TmxTileset::from_node(node, firstgid, None)
     }
+}
+
+impl TmxTileset {
+
+    /// Builds a tileset from a `<tileset>` element, either embedded in a map or
+    /// the root of an external `.tsx` document referenced by `source`.
+    pub fn from_node(node: &Node, firstgid: usize, source: Option<String>) -> Result<TmxTileset, TmxError> {
+        let mut builder = TilesetBuilder::new(node, firstgid, source)?;
+        for child in node.children.iter() {
+            builder.child(child)?;
+        }
+        Ok(builder.finish())
+    }
+
+}
 
+/// Builds a `TmxTileset` from the attributes of a `<tileset>` and then its
+/// children one at a time, like `LayerBuilder`.
+pub struct TilesetBuilder {
+    firstgid: usize,
+    source: Option<String>,
+    tileset: TilesetData,
 }
 
-impl<'a> From<&'a Node> for TmxTileset {
-    fn from(node: &'a Node) -> TmxTileset {
+impl TilesetBuilder {
+
+    pub fn new(node: &Node, firstgid: usize, source: Option<String>) -> Result<TilesetBuilder, TmxError> {
 
         let mut name = String::new();
-        let mut image_file_name = String::new();
         let mut columns = 0;
         let mut tilecount = 0;
         let mut tilewidth = 0;
         let mut tileheight = 0;
-        let mut firstgid = 0;
+        let mut margin = 0;
+        let mut spacing = 0;
+        let mut class = String::new();
+        let mut object_alignment = ObjectAlignment::Unspecified;
 
-        for (ref key, ref value) in &node.attributes {
+        for (key, value) in &node.attributes {
             match key.as_ref() {
                 "name" => {
                     name.clear();
-                    name.push_str(value.clone());
-                }
-                "firstgid" => {
-                    firstgid = value.parse::<usize>().unwrap();
+                    name.push_str(value);
                 }
                 "columns" => {
-                    columns = value.parse::<usize>().unwrap();
+                    columns = node.parse::<usize>(key, value)?;
                 }
                 "tilecount" => {
-                    tilecount = value.parse::<usize>().unwrap();
+                    tilecount = node.parse::<usize>(key, value)?;
                 }
                 "tilewidth" => {
-                    tilewidth = value.parse::<usize>().unwrap();
+                    tilewidth = node.parse::<usize>(key, value)?;
                 }
                 "tileheight" => {
-                    tileheight = value.parse::<usize>().unwrap();
+                    tileheight = node.parse::<usize>(key, value)?;
+                }
+                "margin" => {
+                    margin = node.parse::<usize>(key, value)?;
+                }
+                "spacing" => {
+                    spacing = node.parse::<usize>(key, value)?;
+                }
+                "class" => {
+                    class.push_str(value);
+                }
+                "objectalignment" => {
+                    object_alignment = ObjectAlignment::from_node(node, key, value)?;
                 }
                 _ => {}
             };
         }
 
-        let mut tiles = HashMap::new();
-
-        for ref node in node.children.iter() {
-            match node.name.as_ref() {
-                "image" => {
-//                    let mut width = 0;
-//                    let mut height = 0;
-                    for (ref k, ref v) in &node.attributes {
-                        match k.as_ref() {
-                            "source" => {
-                                image_file_name = v.clone().to_string();
-                            }
-/*
-                             "width" => {
-                                width = v.parse::<usize>().unwrap();
-                            }
-                            "height" => {
-                                height = v.parse::<usize>().unwrap();
-                            }
- */
-                            _ => {}
-                        };
+        Ok(TilesetBuilder {
+            firstgid,
+            source,
+            tileset: TilesetData {
+                name: Arc::from(name),
+                tilecount,
+                columns,
+                rows: tilecount.checked_div(columns).unwrap_or(0),
+                tiles: HashMap::new(),
+                tilewidth,
+                tileheight,
+                margin,
+                spacing,
+                class,
+                tile_offset: (0, 0),
+                object_alignment,
+                terrain_types: Vec::new(),
+                wang_sets: Vec::new(),
+                image_file_name: String::new(),
+                image: None,
+            },
+        })
+    }
+
+    pub fn child(&mut self, node: &Node) -> Result<(), TmxError> {
+        let tileset = &mut self.tileset;
+
+        match node.name.as_ref() {
+            "image" if node.attributes.contains_key("source") => {
+                let tileset_image = TmxImage::from_node(node)?;
+                tileset.image_file_name = tileset_image.source.clone();
+                tileset.image = Some(tileset_image);
+            }
+            "tileoffset" => {
+                if let Some(x) = node.attributes.get("x") {
+                    tileset.tile_offset.0 = node.parse::<i32>("x", x)?;
+                }
+                if let Some(y) = node.attributes.get("y") {
+                    tileset.tile_offset.1 = node.parse::<i32>("y", y)?;
+                }
+            }
+            "terraintypes" => {
+                for terrain in node.children.iter().filter(|child| child.name == "terrain") {
+                    tileset.terrain_types.push(Terrain::from_node(terrain)?);
+                }
+            }
+            "wangsets" => {
+                for wang_set in node.children.iter().filter(|child| child.name == "wangset") {
+                    tileset.wang_sets.push(WangSet::from_node(wang_set)?);
+                }
+            }
+            "tile" => {
+                let id = node.attributes.get("id").ok_or_else(|| node.missing_attribute("id"))?;
+                let mut tile = Tile::new(node.parse::<usize>("id", id)?);
+                tile.properties = Property::properties_from_node(node)?;
+                for (key, value) in &node.attributes {
+                    match key.as_ref() {
+                        "type" | "class" => {
+                            tile.class = value.clone();
+                        }
+                        "probability" => {
+                            tile.probability = node.parse_r32(key, value)?;
+                        }
+                        "terrain" => {
+                            tile.terrain = Some(parse_terrain(node, value)?);
+                        }
+                        _ => {}
                     }
                 }
-                "tile" => {
-                    for (ref k, ref v) in &node.attributes {
-                        match k.as_ref() {
-                            "id" => {
-                                let mut tile = Tile::new(firstgid + v.parse::<usize>().unwrap()); 
-                                tile.properties = Property::properties_from_node(node);
-                                tiles.insert(tile.id.clone(), tile);
-                            }
-                            _ => {}
-                        };
+                for child in node.children.iter() {
+                    match child.name.as_ref() {
+                        "animation" => {
+                            tile.animation = Some(Animation::from_node(child)?);
+                        }
+                        "objectgroup" => {
+                            tile.collision = Some(TmxObjectGroup::try_from(child)?);
+                        }
+                        _ => {}
                     }
                 }
-                _ => {}
+                tileset.tiles.insert(tile.id, tile);
             }
+            _ => {}
         }
 
+        Ok(())
+    }
+
+    pub fn finish(self) -> TmxTileset {
         TmxTileset {
-            name: Rc::new(name),
-            columns,
-            rows: tilecount / columns,
-            tiles,
-            tilewidth,
-            tileheight,
-            image_file_name,
+            firstgid: self.firstgid,
+            source: self.source,
+            data: Arc::new(self.tileset),
         }
     }
+
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct Tile {
+    /// Local id within the tileset.
     pub id: usize,
-    pub properties: HashMap<Rc<String>, Property>,
+    /// `type` in tilesets saved before Tiled 1.9, `class` after.
+    pub class: String,
+    /// Relative chance of being picked by the terrain and wang brushes.
+    pub probability: R32,
+    /// Terrain indices of the top left, top right, bottom left and bottom right corners.
+    pub terrain: Option<[Option<usize>; 4]>,
+    pub properties: HashMap<Arc<str>, Property>,
+    pub animation: Option<Animation>,
+    /// The shapes drawn in Tiled's collision editor, relative to the tile's top left.
+    pub collision: Option<TmxObjectGroup>,
 }
 
 impl Tile {
     pub fn new(id: usize) -> Tile{
         Tile {
             id,
+            class: String::new(),
+            probability: r32(1.0),
+            terrain: None,
             properties: HashMap::new(),
+            animation: None,
+            collision: None,
         }
     }
+
+    pub fn collision_shapes(&self) -> impl Iterator<Item = &Object> {
+        self.collision.iter().flat_map(|group| group.objects.values())
+    }
 }
 
+
+#[cfg(test)]
+mod test {
+
+    use ::noisy_float::prelude::*;
+    use super::*;
+    use ::world::Area;
+    use super::super::fixtures::{map, parse_tmx};
+
+    #[test]
+    fn read_tileset_layout_and_tiles() {
+        let xml = map(r##" <tileset firstgid="1" name="Ground0" tilewidth="16" tileheight="16" tilecount="56" columns="8" objectalignment="bottomleft" margin="1" spacing="2">
+  <tileoffset x="0" y="-8"/>
+  <image source="../sprites/objects/Ground0.png" width="128" height="112"/>
+  <tile id="0" probability="0.25" type="Edge"/>
+  <tile id="1" class="Fill"/>
+ </tileset>"##);
+        let tmx_content = parse_tmx(xml.as_bytes()).unwrap();
+        let ground = tmx_content.get_tileset("Ground0").unwrap();
+
+        assert_eq!(ground.tile_offset, (0, -8));
+        assert_eq!(ground.object_alignment, ObjectAlignment::BottomLeft);
+        assert_eq!((ground.margin, ground.spacing), (1, 2));
+        assert_eq!(ground.source_rect(9), Some(Area::new(r32(19.0), r32(19.0), r32(16.0), r32(16.0))));
+
+        let edge = ground.tile(1).unwrap();
+        assert_eq!(edge.class, "Edge");
+        assert_eq!(edge.probability, r32(0.25));
+        assert_eq!(ground.tile(2).unwrap().class, "Fill");
+        assert_eq!(ground.tile(2).unwrap().probability, r32(1.0));
+    }
+
+}
@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+use std::io::{Read};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::error::TmxError;
+use super::resolver::{FileResolver, Resolver};
+use super::tileset::{TilesetData, TmxTileset};
+
+/// Tilesets read from `.tsx` and `.tsj` files keyed by path, so maps sharing a tileset
+/// only read it once and share its `TilesetData`. Clones share the tilesets read so far,
+/// e.g. with a background load.
+///
+/// Files are read through the cache's `Resolver`, from disk unless the cache
+/// was created `with_resolver`.
+#[derive(Debug, Clone)]
+pub struct TilesetCache {
+    resolver: Arc<dyn Resolver>,
+    tilesets: HashMap<PathBuf, Arc<TilesetData>>,
+}
+
+impl Default for TilesetCache {
+    fn default() -> TilesetCache {
+        TilesetCache::with_resolver(FileResolver)
+    }
+}
+
+impl TilesetCache {
+
+    pub fn new() -> TilesetCache {
+        TilesetCache::default()
+    }
+
+    pub fn with_resolver<R: Resolver + 'static>(resolver: R) -> TilesetCache {
+        TilesetCache {
+            resolver: Arc::new(resolver),
+            tilesets: HashMap::new(),
+        }
+    }
+
+    pub fn resolver(&self) -> Arc<dyn Resolver> {
+        self.resolver.clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tilesets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tilesets.is_empty()
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        self.tilesets.contains_key(&self.resolver.resolve(Path::new(""), path))
+    }
+
+    /// Loads the tileset `source` refers to, relative to `base_dir`, with its
+    /// tile ids starting at `firstgid`.
+    pub fn load(&mut self, base_dir: &Path, source: &str, firstgid: usize) -> Result<TmxTileset, TmxError> {
+        self.load_data(base_dir, source)
+            .map(|data| TmxTileset { firstgid, source: Some(source.to_string()), data })
+            .map_err(|error| TmxError::ExternalTileset {
+                source: source.to_string(),
+                error: Box::new(error),
+            })
+    }
+
+    fn load_data(&mut self, base_dir: &Path, source: &str) -> Result<Arc<TilesetData>, TmxError> {
+        let path = self.resolver.resolve(base_dir, Path::new(source));
+
+        if let Some(data) = self.tilesets.get(&path) {
+            return Ok(data.clone());
+        }
+
+        let mut buffer = Vec::<u8>::new();
+        self.resolver.open(&path)?.read_to_end(&mut buffer)?;
+
+        let document = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("tsj") | Some("json") => super::json::read_tileset_document(&buffer[..])?,
+            _ => super::read_document(&buffer[..])?,
+        };
+        let data = TmxTileset::from_node(document.child("tileset")?, 0, None)?.data;
+        self.tilesets.insert(path, data.clone());
+        Ok(data)
+    }
+
+}
+
+#[cfg(test)]
+mod test {
+
+    use ::noisy_float::prelude::*;
+    use super::*;
+    use super::super::{PropertyEnum, TmxContent};
+    use super::super::fixtures::{fixture_dir, map, write_fixture};
+
+    use std::fs;
+
+    #[test]
+    fn load_external_tilesets() {
+        let dir = fixture_dir("external-tilesets");
+        fs::create_dir_all(dir.join("tilesets")).unwrap();
+        write_fixture(&dir.join("tilesets"), "Wall.tsx", r#"<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.0" name="Wall" tilewidth="16" tileheight="16" tilecount="1020" columns="20">
+ <image source="../../sprites/objects/Wall.png" width="320" height="816"/>
+ <tile id="3">
+  <properties>
+   <property name="Penalty" type="float" value="2.5"/>
+  </properties>
+ </tile>
+</tileset>"#);
+        let town = write_fixture(&dir, "town.tmx", &map(r#" <tileset firstgid="1" source="tilesets/Wall.tsx"/>"#));
+        let castle = write_fixture(&dir, "castle.tmx", &map(r#" <tileset firstgid="1" name="Decor0" tilewidth="16" tileheight="16" tilecount="176" columns="8">
+  <image source="../sprites/objects/Decor0.png" width="128" height="352"/>
+ </tileset>
+ <tileset firstgid="177" source="tilesets/Wall.tsx"/>"#));
+
+        let mut cache = TilesetCache::new();
+        let town = TmxContent::load_with_cache(&town, &mut cache).unwrap();
+        let castle = TmxContent::load_with_cache(&castle, &mut cache).unwrap();
+        assert_eq!(cache.len(), 1);
+        assert!(cache.contains(&dir.join("tilesets/Wall.tsx")));
+
+        let wall = town.get_tileset("Wall").unwrap();
+        assert_eq!((wall.firstgid, wall.columns, wall.rows), (1, 20, 51));
+        assert_eq!(wall.source, Some("tilesets/Wall.tsx".to_string()));
+        assert_eq!(wall.image_file_name, "../../sprites/objects/Wall.png");
+        assert_eq!(wall.property(4, "Penalty"), Some(&PropertyEnum::Float(r32(2.5))));
+
+        let wall = castle.get_tileset("Wall").unwrap();
+        assert_eq!(wall.firstgid, 177);
+        assert_eq!(wall.property(180, "Penalty"), Some(&PropertyEnum::Float(r32(2.5))));
+        assert_eq!(castle.tileset_for_gid(200).unwrap().name, wall.name);
+        assert!(Arc::ptr_eq(&wall.data, &town.get_tileset("Wall").unwrap().data));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn report_missing_external_tileset() {
+        let dir = fixture_dir("missing-tileset");
+        let path = write_fixture(&dir, "town.tmx", &map(r#" <tileset firstgid="1" source="Floor.tsx"/>"#));
+
+        match TmxContent::load(&path) {
+            Err(TmxError::ExternalTileset { ref source, ref error }) => {
+                assert_eq!(source, "Floor.tsx");
+                match **error {
+                    TmxError::Io(_) => {}
+                    ref other => panic!("expected io error, got {:?}", other),
+                }
+            }
+            other => panic!("expected external tileset error, got {:?}", other),
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+}
@@ -0,0 +1,423 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+
+use super::{TmxContent, TilesetCache, load_map_with_cache};
+use super::assets::AssetKind;
+use super::error::TmxError;
+use super::layer::{TileRef, TmxLayer};
+use super::tileset::TmxTileset;
+use super::resolver::{FileResolver, Resolver};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// What a `Diagnostic` was raised by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Check {
+    /// The map could not be loaded at all.
+    Load,
+    GidCoverage,
+    TilesetOverlap,
+    CellCount,
+    LayerSize,
+    ObjectId,
+    MissingFile,
+    UnusedTileset,
+}
+
+/// A problem `validate` found; `subject` names the layer, tileset or object
+/// group it is about, or the map file if the map could not be loaded. Checks
+/// built on top of the TMX format bring their own `C`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic<C = Check> {
+    pub severity: Severity,
+    pub check: C,
+    pub subject: String,
+    pub message: String,
+}
+
+impl<C> Diagnostic<C> {
+
+    fn new<S: Into<String>>(severity: Severity, check: C, subject: S, message: String) -> Diagnostic<C> {
+        Diagnostic {
+            severity,
+            check,
+            subject: subject.into(),
+            message,
+        }
+    }
+
+}
+
+impl<C> fmt::Display for Diagnostic<C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}: '{}': {}", severity, self.subject, self.message)
+    }
+}
+
+/// Checks a loaded map for mistakes Tiled lets through, reading referenced
+/// files from disk.
+pub fn validate(content: &TmxContent) -> Vec<Diagnostic> {
+    validate_with(content, &FileResolver)
+}
+
+/// Like `validate`, checking referenced files with `resolver`.
+pub fn validate_with(content: &TmxContent, resolver: &dyn Resolver) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    check_tileset_overlaps(content, &mut diagnostics);
+    check_layers(content, &mut diagnostics);
+    check_objects(content, &mut diagnostics);
+    check_files(content, resolver, &mut diagnostics);
+
+    diagnostics
+}
+
+/// Loads and validates the map at `path`. A map the parser rejects is
+/// reported as a single error: `Check::CellCount` for tile data with more or
+/// fewer cells than the layer is big, `Check::Load` for anything else.
+pub fn validate_file<P: AsRef<Path>>(path: P, cache: &mut TilesetCache) -> Vec<Diagnostic> {
+    let path = path.as_ref();
+    match load_map_with_cache(path, cache) {
+        Ok(content) => validate_with(&content, &*cache.resolver()),
+        Err(err) => {
+            let check = match err {
+                TmxError::CellCount { .. } => Check::CellCount,
+                _ => Check::Load,
+            };
+            vec![Diagnostic::new(Severity::Error, check, path.display().to_string(), err.to_string())]
+        }
+    }
+}
+
+fn check_tileset_overlaps(content: &TmxContent, diagnostics: &mut Vec<Diagnostic>) {
+    let mut tilesets: Vec<_> = content.tilesets().collect();
+    tilesets.sort_by_key(|tileset| tileset.firstgid);
+
+    for tileset in &tilesets {
+        if tileset.firstgid == 0 {
+            diagnostics.push(Diagnostic::new(Severity::Error, Check::TilesetOverlap, &*tileset.name,
+                "firstgid is 0, which is reserved for empty cells".to_string()));
+        }
+    }
+
+    // a tileset overlaps an earlier one if it starts before the furthest
+    // end seen so far, not just before the end of its neighbour
+    let mut furthest: Option<(usize, &TmxTileset)> = None;
+    for tileset in tilesets {
+        let end = tileset.firstgid + tileset.tilecount;
+        if let Some((furthest_end, other)) = furthest {
+            if furthest_end > tileset.firstgid {
+                diagnostics.push(Diagnostic::new(Severity::Error, Check::TilesetOverlap, &*tileset.name,
+                    format!("gids {}..{} overlap tileset '{}' ({}..{})", tileset.firstgid, end, other.name, other.firstgid, furthest_end)));
+            }
+        }
+        if furthest.is_none_or(|(furthest_end, _)| end > furthest_end) {
+            furthest = Some((end, tileset));
+        }
+    }
+}
+
+fn check_layers(content: &TmxContent, diagnostics: &mut Vec<Diagnostic>) {
+    let mut used = HashMap::new();
+
+    for layer in content.layers() {
+        check_layer_size(content, layer, diagnostics);
+        check_cell_count(layer, diagnostics);
+
+        // unowned gids are reported once per layer, at their first cell
+        let mut unowned: BTreeMap<usize, ((i32, i32), usize)> = BTreeMap::new();
+        for (position, tile) in cells(layer) {
+            if tile.is_empty() {
+                continue;
+            }
+            match content.tileset_for_gid(tile.gid) {
+                Some(tileset) => *used.entry(tileset.firstgid).or_insert(0) += 1,
+                None => unowned.entry(tile.gid).or_insert((position, 0)).1 += 1,
+            }
+        }
+
+        for (gid, ((x, y), count)) in unowned {
+            diagnostics.push(Diagnostic::new(Severity::Error, Check::GidCoverage, &*layer.name,
+                format!("gid {} at {}/{} ({} cells) belongs to no tileset", gid, x, y, count)));
+        }
+    }
+
+    for group in content.object_groups() {
+        let mut objects: Vec<_> = group.objects.values().collect();
+        objects.sort_by_key(|object| object.id);
+        for object in objects {
+            if let Some(ref tile) = object.gid {
+                match content.tileset_for_gid(tile.gid) {
+                    Some(tileset) => *used.entry(tileset.firstgid).or_insert(0) += 1,
+                    None => diagnostics.push(Diagnostic::new(Severity::Error, Check::GidCoverage, &*group.name,
+                        format!("object {} uses gid {}, which belongs to no tileset", object.id, tile.gid))),
+                }
+            }
+        }
+    }
+
+    for tileset in content.tilesets() {
+        if !used.contains_key(&tileset.firstgid) {
+            diagnostics.push(Diagnostic::new(Severity::Info, Check::UnusedTileset, &*tileset.name,
+                "no layer or object uses this tileset".to_string()));
+        }
+    }
+}
+
+fn check_layer_size(content: &TmxContent, layer: &TmxLayer, diagnostics: &mut Vec<Diagnostic>) {
+    if layer.chunks.is_none() && !content.map.infinite && (layer.width, layer.height) != (content.map.width, content.map.height) {
+        diagnostics.push(Diagnostic::new(Severity::Warning, Check::LayerSize, &*layer.name,
+            format!("is {}x{} tiles but the map is {}x{}", layer.width, layer.height, content.map.width, content.map.height)));
+    }
+}
+
+/// The parser already rejects tile data that does not fill its layer or chunk
+/// exactly, see `validate_file`; this catches layers built or changed in code.
+fn check_cell_count(layer: &TmxLayer, diagnostics: &mut Vec<Diagnostic>) {
+    match layer.chunks {
+        Some(ref chunks) => {
+            for chunk in chunks.chunks() {
+                if chunk.tiles.len() != chunk.width * chunk.height {
+                    diagnostics.push(Diagnostic::new(Severity::Error, Check::CellCount, &*layer.name,
+                        format!("chunk at {},{} has {} cells instead of {}", chunk.x, chunk.y, chunk.tiles.len(), chunk.width * chunk.height)));
+                }
+            }
+        }
+        None => {
+            let cells = layer.grid.iter().len();
+            if cells != layer.width * layer.height {
+                diagnostics.push(Diagnostic::new(Severity::Error, Check::CellCount, &*layer.name,
+                    format!("has {} cells instead of {}", cells, layer.width * layer.height)));
+            }
+        }
+    }
+}
+
+/// The cells of a layer with their positions in tiles.
+fn cells(layer: &TmxLayer) -> Vec<((i32, i32), TileRef)> {
+    match layer.chunks {
+        Some(ref chunks) => chunks.chunks()
+            .flat_map(|chunk| {
+                let width = chunk.width.max(1);
+                chunk.tiles.iter().enumerate().map(move |(idx, tile)| {
+                    ((chunk.x + (idx % width) as i32, chunk.y + (idx / width) as i32), *tile)
+                })
+            })
+            .collect(),
+        None => {
+            let width = layer.grid.width().max(1);
+            layer.grid.iter().enumerate()
+                .map(|(idx, tile)| (((idx % width) as i32, (idx / width) as i32), *tile))
+                .collect()
+        }
+    }
+}
+
+fn check_objects(content: &TmxContent, diagnostics: &mut Vec<Diagnostic>) {
+    let mut seen: HashMap<usize, &str> = HashMap::new();
+
+    for group in content.object_groups() {
+        let mut ids: Vec<usize> = group.objects.keys().cloned().collect();
+        ids.sort();
+        for id in ids {
+            match content.map.next_object_id {
+                Some(next_object_id) if id >= next_object_id => {
+                    diagnostics.push(Diagnostic::new(Severity::Error, Check::ObjectId, &*group.name,
+                        format!("object {} is not below the map's nextobjectid {}", id, next_object_id)));
+                }
+                _ => {}
+            }
+            if let Some(other) = seen.insert(id, &group.name) {
+                diagnostics.push(Diagnostic::new(Severity::Error, Check::ObjectId, &*group.name,
+                    format!("object id {} is used in '{}' as well", id, other)));
+            }
+        }
+    }
+}
+
+fn check_files(content: &TmxContent, resolver: &dyn Resolver, diagnostics: &mut Vec<Diagnostic>) {
+    for asset in content.missing_assets(resolver) {
+        let severity = match asset.kind {
+            AssetKind::Tileset | AssetKind::TilesetImage => Severity::Error,
+            AssetKind::ImageLayer => Severity::Warning,
+        };
+        diagnostics.push(Diagnostic::new(severity, Check::MissingFile, asset.name,
+            format!("'{}' does not exist at '{}'", asset.reference, asset.path.display())));
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use ::utils::shipped_maps;
+    use super::super::{Chunk, ChunkedGrid, MemoryResolver, TmxEntry};
+    use super::super::fixtures::{map, parse_tmx};
+    use super::super::layer::Grid;
+
+    fn validate_source(source: &str) -> Vec<Diagnostic> {
+        let mut files = MemoryResolver::new();
+        files.insert("maps/test.tmx", source);
+        files.insert("sprites/Floor.png", vec![0u8]);
+        validate_file("maps/test.tmx", &mut TilesetCache::with_resolver(files))
+    }
+
+    fn checks(diagnostics: &[Diagnostic]) -> Vec<(Severity, Check, &str)> {
+        diagnostics.iter().map(|diagnostic| (diagnostic.severity, diagnostic.check, diagnostic.subject.as_str())).collect()
+    }
+
+    #[test]
+    fn shipped_maps_have_no_errors() {
+        for file_name in shipped_maps() {
+            let diagnostics = validate_file(&file_name, &mut TilesetCache::new());
+            let errors: Vec<String> = diagnostics.iter()
+                .filter(|diagnostic| diagnostic.severity == Severity::Error)
+                .map(Diagnostic::to_string)
+                .collect();
+            assert!(errors.is_empty(), "{}: {:?}", file_name.display(), errors);
+        }
+    }
+
+    #[test]
+    fn report_map_mistakes() {
+        let diagnostics = validate_source(r#"<map version="1.0" orientation="orthogonal" width="2" height="2" tilewidth="16" tileheight="16" nextobjectid="3">
+ <tileset firstgid="1" name="Floor" tilewidth="16" tileheight="16" tilecount="4" columns="2">
+  <image source="../sprites/Floor.png" width="32" height="32"/>
+ </tileset>
+ <tileset firstgid="4" name="Wall" tilewidth="16" tileheight="16" tilecount="4" columns="2">
+  <image source="../sprites/Wall.png" width="32" height="32"/>
+ </tileset>
+ <tileset firstgid="20" name="Unused" tilewidth="16" tileheight="16" tilecount="1" columns="1"/>
+ <layer name="Ground" width="2" height="2">
+  <data encoding="csv">1,9,9,4</data>
+ </layer>
+ <layer name="Roof" width="1" height="1">
+  <data encoding="csv">0</data>
+ </layer>
+ <objectgroup name="Spawns">
+  <object id="1" x="0" y="0"/>
+  <object id="3" gid="12" x="0" y="0" width="16" height="16"/>
+ </objectgroup>
+ <objectgroup name="Portals">
+  <object id="1" x="0" y="0"/>
+ </objectgroup>
+ <imagelayer name="Light"><image source="light.png"/></imagelayer>
+</map>"#);
+
+        assert_eq!(checks(&diagnostics), vec![
+            (Severity::Error, Check::TilesetOverlap, "Wall"),
+            (Severity::Error, Check::GidCoverage, "Ground"),
+            (Severity::Warning, Check::LayerSize, "Roof"),
+            (Severity::Error, Check::GidCoverage, "Spawns"),
+            (Severity::Info, Check::UnusedTileset, "Unused"),
+            (Severity::Error, Check::ObjectId, "Spawns"),
+            (Severity::Error, Check::ObjectId, "Portals"),
+            (Severity::Error, Check::MissingFile, "Wall"),
+            (Severity::Warning, Check::MissingFile, "Light"),
+        ]);
+        assert_eq!(diagnostics[0].to_string(), "error: 'Wall': gids 4..8 overlap tileset 'Floor' (1..5)");
+        assert_eq!(diagnostics[1].message, "gid 9 at 1/0 (2 cells) belongs to no tileset");
+        assert_eq!(diagnostics[6].message, "object id 1 is used in 'Spawns' as well");
+    }
+
+    #[test]
+    fn report_overlaps_with_any_earlier_tileset() {
+        let diagnostics = validate_source(r#"<map version="1.0" orientation="orthogonal" width="1" height="1" tilewidth="16" tileheight="16">
+ <tileset firstgid="1" name="A" tilewidth="16" tileheight="16" tilecount="100" columns="10"/>
+ <tileset firstgid="10" name="B" tilewidth="16" tileheight="16" tilecount="2" columns="2"/>
+ <tileset firstgid="20" name="C" tilewidth="16" tileheight="16" tilecount="2" columns="2"/>
+ <tileset firstgid="101" name="D" tilewidth="16" tileheight="16" tilecount="2" columns="2"/>
+ <layer name="Ground" width="1" height="1">
+  <data encoding="csv">0</data>
+ </layer>
+</map>"#);
+
+        let overlaps: Vec<String> = diagnostics.iter()
+            .filter(|diagnostic| diagnostic.check == Check::TilesetOverlap)
+            .map(Diagnostic::to_string)
+            .collect();
+        assert_eq!(overlaps, vec![
+            "error: 'B': gids 10..12 overlap tileset 'A' (1..101)",
+            "error: 'C': gids 20..22 overlap tileset 'A' (1..101)",
+        ]);
+    }
+
+    #[test]
+    fn skip_object_ids_without_nextobjectid() {
+        let diagnostics = validate_source(r#"<map version="1.0" orientation="orthogonal" width="1" height="1" tilewidth="16" tileheight="16">
+ <objectgroup name="Spawns">
+  <object id="1" x="0" y="0"/>
+  <object id="7" x="0" y="0"/>
+ </objectgroup>
+ <objectgroup name="Portals">
+  <object id="7" x="0" y="0"/>
+ </objectgroup>
+</map>"#);
+
+        assert_eq!(checks(&diagnostics), vec![(Severity::Error, Check::ObjectId, "Portals")]);
+        assert_eq!(diagnostics[0].message, "object id 7 is used in 'Spawns' as well");
+    }
+
+    #[test]
+    fn report_cell_count_mismatch() {
+        let diagnostics = validate_source(r#"<map version="1.0" orientation="orthogonal" width="2" height="2" tilewidth="16" tileheight="16">
+ <layer name="Ground" width="2" height="2">
+  <data encoding="csv">1,1,1</data>
+ </layer>
+</map>"#);
+
+        assert_eq!(checks(&diagnostics), vec![(Severity::Error, Check::CellCount, "maps/test.tmx")]);
+        assert!(diagnostics[0].message.ends_with("<data> has 3 cells instead of 4"), "{}", diagnostics[0].message);
+
+        let diagnostics = validate_source(r#"<map version="1.0" orientation="orthogonal" width="2" height="2" tilewidth="16" tileheight="16">
+ <layer name="Ground" width="2" height="2">
+  <data encoding="csv">1,1,x,1</data>
+ </layer>
+</map>"#);
+        assert_eq!(checks(&diagnostics), vec![(Severity::Error, Check::Load, "maps/test.tmx")]);
+    }
+
+    #[test]
+    fn report_cell_count_of_layers_built_in_code() {
+        let mut content = parse_tmx(map(r#" <layer name="Ground" width="2" height="2">
+  <data encoding="csv">0,0,0,0</data>
+ </layer>
+ <layer name="Chunks" width="2" height="2">
+  <data encoding="csv">0,0,0,0</data>
+ </layer>"#).as_bytes()).unwrap();
+        assert!(validate(&content).is_empty());
+
+        for entry in content.entries.iter_mut() {
+            if let TmxEntry::Layer(ref mut layer) = *entry {
+                if &*layer.name == "Ground" {
+                    layer.grid = Grid::new(2, 1);
+                } else {
+                    layer.chunks = Some(ChunkedGrid::new(vec![
+                        Chunk { x: 0, y: 0, width: 2, height: 2, tiles: vec![TileRef::default(); 4] },
+                        Chunk { x: 2, y: 0, width: 2, height: 2, tiles: vec![TileRef::default(); 3] },
+                    ]));
+                }
+            }
+        }
+
+        let diagnostics = validate(&content);
+        assert_eq!(checks(&diagnostics), vec![
+            (Severity::Error, Check::CellCount, "Ground"),
+            (Severity::Error, Check::CellCount, "Chunks"),
+        ]);
+        assert_eq!(diagnostics[0].message, "has 2 cells instead of 4");
+        assert_eq!(diagnostics[1].message, "chunk at 2,0 has 3 cells instead of 4");
+    }
+
+}
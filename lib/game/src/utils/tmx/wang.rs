@@ -0,0 +1,242 @@
+use ::noisy_float::prelude::*;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::Node;
+use super::color::Color;
+use super::error::TmxError;
+use super::property::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WangSetType {
+    Corner,
+    Edge,
+    Mixed,
+}
+
+/// The colors around a tile, clockwise from the top edge: top, top right,
+/// right, bottom right, bottom, bottom left, left, top left. 0 means no color,
+/// otherwise it is the 1-based index into `WangSet::colors`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct WangId(pub [u8; 8]);
+
+impl WangId {
+
+    pub fn parse(node: &Node, value: &str) -> Result<WangId, TmxError> {
+        let mut id = [0; 8];
+        let parts: Vec<&str> = value.split(',').collect();
+
+        if parts.len() != 8 {
+            return Err(node.invalid_value("wangid", value));
+        }
+
+        for (color, part) in id.iter_mut().zip(parts) {
+            *color = node.parse::<u8>("wangid", part)?;
+        }
+
+        Ok(WangId(id))
+    }
+
+    pub fn from_edges(top: u8, right: u8, bottom: u8, left: u8) -> WangId {
+        WangId([top, 0, right, 0, bottom, 0, left, 0])
+    }
+
+    pub fn from_corners(top_right: u8, bottom_right: u8, bottom_left: u8, top_left: u8) -> WangId {
+        WangId([0, top_right, 0, bottom_right, 0, bottom_left, 0, top_left])
+    }
+
+    /// Top, right, bottom and left edge colors.
+    pub fn edges(&self) -> [u8; 4] {
+        [self.0[0], self.0[2], self.0[4], self.0[6]]
+    }
+
+    /// Top right, bottom right, bottom left and top left corner colors.
+    pub fn corners(&self) -> [u8; 4] {
+        [self.0[1], self.0[3], self.0[5], self.0[7]]
+    }
+
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct WangColor {
+    pub name: String,
+    pub class: String,
+    pub color: Color,
+    /// Local id of the tile representing the color.
+    pub tile: Option<usize>,
+    pub probability: R32,
+    pub properties: HashMap<Arc<str>, Property>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct WangSet {
+    pub name: String,
+    pub class: String,
+    pub kind: WangSetType,
+    /// Local id of the tile representing the set.
+    pub tile: Option<usize>,
+    pub colors: Vec<WangColor>,
+    /// Wang ids keyed by local tile id.
+    pub tiles: HashMap<usize, WangId>,
+    pub properties: HashMap<Arc<str>, Property>,
+}
+
+impl WangSet {
+
+    pub fn color(&self, index: u8) -> Option<&WangColor> {
+        if index == 0 {
+            return None;
+        }
+        self.colors.get(index as usize - 1)
+    }
+
+    /// Local ids of the tiles whose colors are exactly `id`, in ascending order.
+    pub fn tiles_matching(&self, id: &WangId) -> Vec<usize> {
+        let mut tiles: Vec<usize> = self.tiles.iter()
+            .filter(|&(_, wang_id)| wang_id == id)
+            .map(|(&tile, _)| tile)
+            .collect();
+        tiles.sort();
+        tiles
+    }
+
+    pub fn from_node(node: &Node) -> Result<WangSet, TmxError> {
+
+        let mut name = String::new();
+        let mut class = String::new();
+        let mut kind = WangSetType::Mixed;
+        let mut tile = None;
+
+        for (key, value) in &node.attributes {
+            match key.as_ref() {
+                "name" => {
+                    name.push_str(value);
+                }
+                "class" => {
+                    class.push_str(value);
+                }
+                "type" => {
+                    kind = match value.as_ref() {
+                        "corner" => WangSetType::Corner,
+                        "edge" => WangSetType::Edge,
+                        "mixed" => WangSetType::Mixed,
+                        _ => return Err(node.invalid_value(key, value)),
+                    };
+                }
+                "tile" => {
+                    tile = node.parse_local_tile(key, value)?;
+                }
+                _ => {}
+            }
+        }
+
+        let mut colors = Vec::new();
+        let mut tiles = HashMap::new();
+
+        for child in node.children.iter() {
+            match child.name.as_ref() {
+                "wangcolor" => {
+                    colors.push(wang_color_from_node(child)?);
+                }
+                "wangtile" => {
+                    let tileid = child.attributes.get("tileid").ok_or_else(|| child.missing_attribute("tileid"))?;
+                    let wangid = child.attributes.get("wangid").ok_or_else(|| child.missing_attribute("wangid"))?;
+                    tiles.insert(child.parse::<usize>("tileid", tileid)?, WangId::parse(child, wangid)?);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(WangSet {
+            name,
+            class,
+            kind,
+            tile,
+            colors,
+            tiles,
+            properties: Property::properties_from_node(node)?,
+        })
+    }
+
+}
+
+fn wang_color_from_node(node: &Node) -> Result<WangColor, TmxError> {
+
+    let mut name = String::new();
+    let mut class = String::new();
+    let mut color = None;
+    let mut tile = None;
+    let mut probability = r32(1.0);
+
+    for (key, value) in &node.attributes {
+        match key.as_ref() {
+            "name" => {
+                name.push_str(value);
+            }
+            "class" => {
+                class.push_str(value);
+            }
+            "color" => {
+                color = Some(Color::from_node(node, key, value)?);
+            }
+            "tile" => {
+                tile = node.parse_local_tile(key, value)?;
+            }
+            "probability" => {
+                probability = node.parse_r32(key, value)?;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(WangColor {
+        name,
+        class,
+        color: color.ok_or_else(|| node.missing_attribute("color"))?,
+        tile,
+        probability,
+        properties: Property::properties_from_node(node)?,
+    })
+}
+
+#[cfg(test)]
+mod test {
+
+    use ::noisy_float::prelude::*;
+    use super::*;
+    use super::super::fixtures::{map, parse_tmx};
+
+    #[test]
+    fn read_wang_sets() {
+        let xml = map(r##" <tileset firstgid="1" name="Ground0" tilewidth="16" tileheight="16" tilecount="56" columns="8">
+  <image source="../sprites/objects/Ground0.png" width="128" height="112"/>
+  <wangsets>
+   <wangset name="Paths" type="corner" tile="-1">
+    <wangcolor name="Grass" color="#00ff00" tile="9" probability="1"/>
+    <wangcolor name="Dirt" color="#804000" tile="-1" probability="0.5"/>
+    <wangtile tileid="0" wangid="0,1,0,1,0,1,0,1"/>
+    <wangtile tileid="1" wangid="0,1,0,2,0,2,0,1"/>
+    <wangtile tileid="5" wangid="0,1,0,1,0,1,0,1"/>
+   </wangset>
+  </wangsets>
+ </tileset>"##);
+        let tmx_content = parse_tmx(xml.as_bytes()).unwrap();
+        let ground = tmx_content.get_tileset("Ground0").unwrap();
+
+        let paths = ground.get_wang_set("Paths").unwrap();
+        assert_eq!(paths.kind, WangSetType::Corner);
+        assert_eq!(paths.tile, None);
+        assert_eq!(paths.colors.len(), 2);
+        assert_eq!(paths.color(1).unwrap().tile, Some(9));
+        assert_eq!(paths.color(2).unwrap().name, "Dirt");
+        assert_eq!(paths.color(2).unwrap().probability, r32(0.5));
+        assert!(paths.color(0).is_none());
+
+        let grass = WangId::from_corners(1, 1, 1, 1);
+        assert_eq!(paths.tiles_matching(&grass), vec![0, 5]);
+        assert_eq!(paths.tiles.get(&1).unwrap().corners(), [1, 2, 2, 1]);
+        assert_eq!(paths.tiles.get(&1).unwrap().edges(), [0, 0, 0, 0]);
+    }
+
+}
@@ -0,0 +1,758 @@
+extern crate base64;
+extern crate flate2;
+extern crate xml;
+
+use ::noisy_float::prelude::*;
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use self::base64::Engine;
+use self::base64::engine::general_purpose::STANDARD;
+use self::flate2::Compression;
+use self::flate2::write::ZlibEncoder;
+use self::xml::common::XmlVersion;
+use self::xml::writer::{EmitterConfig, EventWriter, XmlEvent};
+
+use super::{TmxContent, TmxEntry};
+use super::chunk::ChunkedGrid;
+use super::common::LayerCommon;
+use super::error::TmxError;
+use super::group::TmxGroup;
+use super::image::TmxImage;
+use super::imagelayer::TmxImageLayer;
+use super::layer::{TileRef, TmxLayer};
+use super::map::{Orientation, RenderOrder};
+use super::objectgroup::*;
+use super::property::*;
+use super::tileset::{ObjectAlignment, Tile, TmxTileset};
+use super::wang::*;
+
+use ::world::Point;
+
+/// How the tile data of layers is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataEncoding {
+    Csv,
+    Base64Zlib,
+}
+
+impl TmxContent {
+
+    /// Writes the map as a `.tmx` document. External tilesets are written as
+    /// references to their `source`, which is expected to exist already.
+    pub fn write<W: Write>(&self, out: W, encoding: DataEncoding) -> Result<(), TmxError> {
+        let mut writer = TmxWriter::new(out, encoding)?;
+        writer.map(self)?;
+        writer.finish()
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P, encoding: DataEncoding) -> Result<(), TmxError> {
+        self.write(BufWriter::new(File::create(path)?), encoding)
+    }
+
+}
+
+impl TmxTileset {
+
+    /// Writes the tileset as a standalone `.tsx` document.
+    pub fn write_tsx<W: Write>(&self, out: W) -> Result<(), TmxError> {
+        let mut writer = TmxWriter::new(out, DataEncoding::Csv)?;
+        writer.tileset(self, None)?;
+        writer.finish()
+    }
+
+    pub fn save_tsx<P: AsRef<Path>>(&self, path: P) -> Result<(), TmxError> {
+        self.write_tsx(BufWriter::new(File::create(path)?))
+    }
+
+}
+
+struct TmxWriter<W: Write> {
+    inner: EventWriter<W>,
+    encoding: DataEncoding,
+}
+
+impl<W: Write> TmxWriter<W> {
+
+    fn new(out: W, encoding: DataEncoding) -> Result<TmxWriter<W>, TmxError> {
+        let mut inner = EmitterConfig::new()
+            .perform_indent(true)
+            .indent_string(" ")
+            .create_writer(out);
+
+        inner.write(XmlEvent::StartDocument {
+            version: XmlVersion::Version10,
+            encoding: Some("UTF-8"),
+            standalone: None,
+        })?;
+
+        Ok(TmxWriter { inner, encoding })
+    }
+
+    fn finish(self) -> Result<(), TmxError> {
+        self.inner.into_inner().flush()?;
+        Ok(())
+    }
+
+    fn start(&mut self, name: &str, attributes: &[(&str, String)]) -> Result<(), TmxError> {
+        let mut element = XmlEvent::start_element(name);
+        for &(key, ref value) in attributes {
+            element = element.attr(key, value);
+        }
+        self.inner.write(element)?;
+        Ok(())
+    }
+
+    fn end(&mut self) -> Result<(), TmxError> {
+        self.inner.write(XmlEvent::end_element())?;
+        Ok(())
+    }
+
+    fn empty(&mut self, name: &str, attributes: &[(&str, String)]) -> Result<(), TmxError> {
+        self.start(name, attributes)?;
+        self.end()
+    }
+
+    fn text(&mut self, text: &str) -> Result<(), TmxError> {
+        self.inner.write(XmlEvent::characters(text))?;
+        Ok(())
+    }
+
+    fn map(&mut self, content: &TmxContent) -> Result<(), TmxError> {
+        let map = &content.map;
+
+        let mut attributes = vec![
+            ("version", map.version.clone()),
+        ];
+        if let Some(ref tiled_version) = map.tiled_version {
+            attributes.push(("tiledversion", tiled_version.clone()));
+        }
+        attributes.push(("orientation", orientation_name(map.orientation).to_string()));
+        attributes.push(("renderorder", render_order_name(map.render_order).to_string()));
+        attributes.push(("width", map.width.to_string()));
+        attributes.push(("height", map.height.to_string()));
+        attributes.push(("tilewidth", map.tilewidth.to_string()));
+        attributes.push(("tileheight", map.tileheight.to_string()));
+        attributes.push(("infinite", flag(map.infinite)));
+        if let Some(color) = map.background_color {
+            attributes.push(("backgroundcolor", color.to_string()));
+        }
+        if let Some(next_layer_id) = map.next_layer_id {
+            attributes.push(("nextlayerid", next_layer_id.to_string()));
+        }
+        if let Some(next_object_id) = map.next_object_id {
+            attributes.push(("nextobjectid", next_object_id.to_string()));
+        }
+
+        self.start("map", &attributes)?;
+        self.properties(&map.properties)?;
+        self.entries(content.entries())?;
+        self.end()
+    }
+
+    fn entries(&mut self, entries: &[TmxEntry]) -> Result<(), TmxError> {
+        for entry in entries {
+            match *entry {
+                TmxEntry::Layer(ref layer) => self.layer(layer)?,
+                TmxEntry::Tileset(ref tileset) => match tileset.source {
+                    Some(ref source) => self.empty("tileset", &[
+                        ("firstgid", tileset.firstgid.to_string()),
+                        ("source", source.clone()),
+                    ])?,
+                    None => self.tileset(tileset, Some(tileset.firstgid))?,
+                },
+                TmxEntry::ObjectGroup(ref object_group) => self.object_group(object_group)?,
+                TmxEntry::ImageLayer(ref image_layer) => self.image_layer(image_layer)?,
+                TmxEntry::Group(ref group) => self.group(group)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn tileset(&mut self, tileset: &TmxTileset, firstgid: Option<usize>) -> Result<(), TmxError> {
+        let mut attributes = Vec::new();
+        if let Some(firstgid) = firstgid {
+            attributes.push(("firstgid", firstgid.to_string()));
+        }
+        attributes.push(("name", tileset.name.to_string()));
+        if !tileset.class.is_empty() {
+            attributes.push(("class", tileset.class.clone()));
+        }
+        attributes.push(("tilewidth", tileset.tilewidth.to_string()));
+        attributes.push(("tileheight", tileset.tileheight.to_string()));
+        if tileset.spacing != 0 {
+            attributes.push(("spacing", tileset.spacing.to_string()));
+        }
+        if tileset.margin != 0 {
+            attributes.push(("margin", tileset.margin.to_string()));
+        }
+        attributes.push(("tilecount", tileset.tilecount.to_string()));
+        attributes.push(("columns", tileset.columns.to_string()));
+        if tileset.object_alignment != ObjectAlignment::Unspecified {
+            attributes.push(("objectalignment", object_alignment_name(tileset.object_alignment).to_string()));
+        }
+
+        self.start("tileset", &attributes)?;
+
+        if tileset.tile_offset != (0, 0) {
+            self.empty("tileoffset", &[
+                ("x", tileset.tile_offset.0.to_string()),
+                ("y", tileset.tile_offset.1.to_string()),
+            ])?;
+        }
+
+        match tileset.image {
+            Some(ref image) => self.image(image)?,
+            None if !tileset.image_file_name.is_empty() => self.empty("image", &[("source", tileset.image_file_name.clone())])?,
+            None => {}
+        }
+
+        if !tileset.terrain_types.is_empty() {
+            self.start("terraintypes", &[])?;
+            for terrain in tileset.terrain_types.iter() {
+                self.start("terrain", &[
+                    ("name", terrain.name.clone()),
+                    ("tile", local_tile(terrain.tile)),
+                ])?;
+                self.properties(&terrain.properties)?;
+                self.end()?;
+            }
+            self.end()?;
+        }
+
+        let mut tiles: Vec<&Tile> = tileset.tiles.values().collect();
+        tiles.sort_by_key(|tile| tile.id);
+        for tile in tiles {
+            self.tile(tile)?;
+        }
+
+        if !tileset.wang_sets.is_empty() {
+            self.start("wangsets", &[])?;
+            for wang_set in tileset.wang_sets.iter() {
+                self.wang_set(wang_set)?;
+            }
+            self.end()?;
+        }
+
+        self.end()
+    }
+
+    fn tile(&mut self, tile: &Tile) -> Result<(), TmxError> {
+        let mut attributes = vec![("id", tile.id.to_string())];
+        if !tile.class.is_empty() {
+            attributes.push(("type", tile.class.clone()));
+        }
+        if let Some(terrain) = tile.terrain {
+            let corners: Vec<String> = terrain.iter()
+                .map(|corner| corner.map(|idx| idx.to_string()).unwrap_or_default())
+                .collect();
+            attributes.push(("terrain", corners.join(",")));
+        }
+        if tile.probability != r32(1.0) {
+            attributes.push(("probability", tile.probability.to_string()));
+        }
+
+        self.start("tile", &attributes)?;
+        self.properties(&tile.properties)?;
+        if let Some(ref collision) = tile.collision {
+            self.object_group(collision)?;
+        }
+        if let Some(ref animation) = tile.animation {
+            self.start("animation", &[])?;
+            for frame in animation.frames.iter() {
+                self.empty("frame", &[
+                    ("tileid", frame.tileid.to_string()),
+                    ("duration", frame.duration.as_millis().to_string()),
+                ])?;
+            }
+            self.end()?;
+        }
+        self.end()
+    }
+
+    fn wang_set(&mut self, wang_set: &WangSet) -> Result<(), TmxError> {
+        let mut attributes = vec![("name", wang_set.name.clone())];
+        if !wang_set.class.is_empty() {
+            attributes.push(("class", wang_set.class.clone()));
+        }
+        attributes.push(("type", wang_set_type_name(wang_set.kind).to_string()));
+        attributes.push(("tile", local_tile(wang_set.tile)));
+
+        self.start("wangset", &attributes)?;
+        self.properties(&wang_set.properties)?;
+
+        for color in wang_set.colors.iter() {
+            let mut attributes = vec![("name", color.name.clone())];
+            if !color.class.is_empty() {
+                attributes.push(("class", color.class.clone()));
+            }
+            attributes.push(("color", color.color.to_string()));
+            attributes.push(("tile", local_tile(color.tile)));
+            attributes.push(("probability", color.probability.to_string()));
+            self.start("wangcolor", &attributes)?;
+            self.properties(&color.properties)?;
+            self.end()?;
+        }
+
+        let mut tiles: Vec<(&usize, &WangId)> = wang_set.tiles.iter().collect();
+        tiles.sort_by_key(|&(tileid, _)| *tileid);
+        for (tileid, wang_id) in tiles {
+            let colors: Vec<String> = wang_id.0.iter().map(|color| color.to_string()).collect();
+            self.empty("wangtile", &[
+                ("tileid", tileid.to_string()),
+                ("wangid", colors.join(",")),
+            ])?;
+        }
+
+        self.end()
+    }
+
+    fn image(&mut self, image: &TmxImage) -> Result<(), TmxError> {
+        let mut attributes = vec![("source", image.source.clone())];
+        if let Some(trans) = image.trans {
+            attributes.push(("trans", format!("{:02x}{:02x}{:02x}", trans.r, trans.g, trans.b)));
+        }
+        if let Some(width) = image.width {
+            attributes.push(("width", width.to_string()));
+        }
+        if let Some(height) = image.height {
+            attributes.push(("height", height.to_string()));
+        }
+        self.empty("image", &attributes)
+    }
+
+    fn layer(&mut self, layer: &TmxLayer) -> Result<(), TmxError> {
+        let mut attributes = vec![
+            ("name", layer.name.to_string()),
+            ("width", layer.width.to_string()),
+            ("height", layer.height.to_string()),
+        ];
+        common_attributes(&layer.common, &mut attributes);
+
+        self.start("layer", &attributes)?;
+        self.properties(&layer.properties)?;
+
+        let mut attributes = vec![];
+        match self.encoding {
+            DataEncoding::Csv => attributes.push(("encoding", "csv".to_string())),
+            DataEncoding::Base64Zlib => {
+                attributes.push(("encoding", "base64".to_string()));
+                attributes.push(("compression", "zlib".to_string()));
+            }
+        }
+        self.start("data", &attributes)?;
+        match layer.chunks {
+            Some(ref chunks) => self.chunks(chunks)?,
+            None => {
+                let tiles: Vec<TileRef> = layer.grid.iter().cloned().collect();
+                let data = self.encode(&tiles, layer.grid.width())?;
+                self.text(&data)?;
+            }
+        }
+        self.end()?;
+
+        self.end()
+    }
+
+    fn chunks(&mut self, chunks: &ChunkedGrid) -> Result<(), TmxError> {
+        let mut sorted: Vec<_> = chunks.chunks().collect();
+        sorted.sort_by_key(|chunk| (chunk.y, chunk.x));
+
+        for chunk in sorted {
+            self.start("chunk", &[
+                ("x", chunk.x.to_string()),
+                ("y", chunk.y.to_string()),
+                ("width", chunk.width.to_string()),
+                ("height", chunk.height.to_string()),
+            ])?;
+            let data = self.encode(&chunk.tiles, chunk.width)?;
+            self.text(&data)?;
+            self.end()?;
+        }
+
+        Ok(())
+    }
+
+    fn encode(&self, tiles: &[TileRef], width: usize) -> Result<String, TmxError> {
+        match self.encoding {
+            DataEncoding::Csv => {
+                let rows: Vec<String> = tiles.chunks(width.max(1))
+                    .map(|row| row.iter().map(|tile| tile.raw().to_string()).collect::<Vec<String>>().join(","))
+                    .collect();
+                Ok(format!("\n{}\n", rows.join(",\n")))
+            }
+            DataEncoding::Base64Zlib => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+                for tile in tiles {
+                    encoder.write_all(&tile.raw().to_le_bytes())?;
+                }
+                Ok(STANDARD.encode(encoder.finish()?))
+            }
+        }
+    }
+
+    fn object_group(&mut self, object_group: &TmxObjectGroup) -> Result<(), TmxError> {
+        let mut attributes = vec![];
+        if !object_group.name.is_empty() {
+            attributes.push(("name", object_group.name.to_string()));
+        }
+        common_attributes(&object_group.common, &mut attributes);
+
+        self.start("objectgroup", &attributes)?;
+        self.properties(&object_group.properties)?;
+
+        let mut objects: Vec<&Object> = object_group.objects.values().collect();
+        objects.sort_by_key(|object| object.id);
+        for object in objects {
+            self.object(object)?;
+        }
+
+        self.end()
+    }
+
+    fn object(&mut self, object: &Object) -> Result<(), TmxError> {
+        let mut attributes = vec![("id", object.id.to_string())];
+        if !object.name.is_empty() {
+            attributes.push(("name", object.name.clone()));
+        }
+        if !object.class.is_empty() {
+            let attribute = match object.class_attribute {
+                ClassAttribute::Type => "type",
+                ClassAttribute::Class => "class",
+            };
+            attributes.push((attribute, object.class.clone()));
+        }
+        if let Some(gid) = object.gid {
+            attributes.push(("gid", gid.raw().to_string()));
+        }
+        attributes.push(("x", object.area.x.to_string()));
+        attributes.push(("y", object.area.y.to_string()));
+        // a rectangle without a size would be read back as a point
+        let sized = object.area.width != r32(0.0) || object.area.height != r32(0.0);
+        if sized || object.shape == ObjectShape::Rectangle {
+            attributes.push(("width", object.area.width.to_string()));
+            attributes.push(("height", object.area.height.to_string()));
+        }
+        if object.rotation != r32(0.0) {
+            attributes.push(("rotation", object.rotation.to_string()));
+        }
+        if !object.visible {
+            attributes.push(("visible", flag(false)));
+        }
+
+        self.start("object", &attributes)?;
+        self.properties(&object.properties)?;
+        match object.shape {
+            ObjectShape::Rectangle | ObjectShape::Tile => {}
+            ObjectShape::Ellipse => self.empty("ellipse", &[])?,
+            ObjectShape::Point => self.empty("point", &[])?,
+            ObjectShape::Polygon(ref points) => self.empty("polygon", &[("points", points_value(points))])?,
+            ObjectShape::Polyline(ref points) => self.empty("polyline", &[("points", points_value(points))])?,
+            ObjectShape::Text(ref text) => self.object_text(text)?,
+        }
+        self.end()
+    }
+
+    fn object_text(&mut self, text: &TmxText) -> Result<(), TmxError> {
+        let attributes = vec![
+            ("fontfamily", text.font_family.clone()),
+            ("pixelsize", text.pixel_size.to_string()),
+            ("wrap", flag(text.wrap)),
+            ("color", text.color.to_string()),
+            ("bold", flag(text.bold)),
+            ("italic", flag(text.italic)),
+            ("underline", flag(text.underline)),
+            ("strikeout", flag(text.strikeout)),
+            ("halign", halign_name(text.halign).to_string()),
+            ("valign", valign_name(text.valign).to_string()),
+        ];
+        self.start("text", &attributes)?;
+        self.text(&text.text)?;
+        self.end()
+    }
+
+    fn image_layer(&mut self, image_layer: &TmxImageLayer) -> Result<(), TmxError> {
+        let mut attributes = vec![("name", image_layer.name.to_string())];
+        common_attributes(&image_layer.common, &mut attributes);
+        if image_layer.repeat_x {
+            attributes.push(("repeatx", flag(true)));
+        }
+        if image_layer.repeat_y {
+            attributes.push(("repeaty", flag(true)));
+        }
+
+        self.start("imagelayer", &attributes)?;
+        self.properties(&image_layer.properties)?;
+        if let Some(ref image) = image_layer.image {
+            self.image(image)?;
+        }
+        self.end()
+    }
+
+    fn group(&mut self, group: &TmxGroup) -> Result<(), TmxError> {
+        let mut attributes = vec![("name", group.name.to_string())];
+        common_attributes(&group.common, &mut attributes);
+
+        self.start("group", &attributes)?;
+        self.properties(&group.properties)?;
+        self.entries(&group.children)?;
+        self.end()
+    }
+
+    fn properties(&mut self, properties: &HashMap<Arc<str>, Property>) -> Result<(), TmxError> {
+        if properties.is_empty() {
+            return Ok(());
+        }
+
+        let mut sorted: Vec<&Property> = properties.values().collect();
+        sorted.sort_by(|a, b| a.name.cmp(&b.name));
+
+        self.start("properties", &[])?;
+        for property in sorted {
+            self.property(property)?;
+        }
+        self.end()
+    }
+
+    fn property(&mut self, property: &Property) -> Result<(), TmxError> {
+        let name = ("name", property.name.to_string());
+        let (type_, value) = match property.value {
+            PropertyEnum::String(ref value) if value.contains('\n') => {
+                // multi-line strings go into the element text, like Tiled does
+                self.start("property", &[name])?;
+                self.text(value)?;
+                return self.end();
+            }
+            PropertyEnum::Class { ref propertytype, ref properties } => {
+                self.start("property", &[name, ("type", "class".to_string()), ("propertytype", propertytype.clone())])?;
+                self.properties(properties)?;
+                return self.end();
+            }
+            PropertyEnum::String(ref value) => ("string", value.clone()),
+            PropertyEnum::Float(value) => ("float", value.to_string()),
+            PropertyEnum::Int(value) => ("int", value.to_string()),
+            PropertyEnum::Bool(value) => ("bool", value.to_string()),
+            PropertyEnum::Color(value) => ("color", value.map(|color| color.to_string()).unwrap_or_default()),
+            PropertyEnum::File(ref value) => ("file", value.clone()),
+            PropertyEnum::Object(value) => ("object", value.to_string()),
+        };
+
+        if type_ == "string" {
+            self.empty("property", &[name, ("value", value)])
+        } else {
+            self.empty("property", &[name, ("type", type_.to_string()), ("value", value)])
+        }
+    }
+
+}
+
+fn common_attributes(common: &LayerCommon, attributes: &mut Vec<(&str, String)>) {
+    let defaults = LayerCommon::default();
+    if let Some(id) = common.id {
+        // Tiled writes the id before the name
+        attributes.insert(0, ("id", id.to_string()));
+    }
+    if common.visible != defaults.visible {
+        attributes.push(("visible", flag(common.visible)));
+    }
+    if common.opacity != defaults.opacity {
+        attributes.push(("opacity", common.opacity.to_string()));
+    }
+    if let Some(tint) = common.tint_color {
+        attributes.push(("tintcolor", tint.to_string()));
+    }
+    if common.offsetx != defaults.offsetx {
+        attributes.push(("offsetx", common.offsetx.to_string()));
+    }
+    if common.offsety != defaults.offsety {
+        attributes.push(("offsety", common.offsety.to_string()));
+    }
+    if common.parallaxx != defaults.parallaxx {
+        attributes.push(("parallaxx", common.parallaxx.to_string()));
+    }
+    if common.parallaxy != defaults.parallaxy {
+        attributes.push(("parallaxy", common.parallaxy.to_string()));
+    }
+}
+
+fn flag(value: bool) -> String {
+    if value { "1".to_string() } else { "0".to_string() }
+}
+
+fn local_tile(tile: Option<usize>) -> String {
+    tile.map_or_else(|| "-1".to_string(), |tile| tile.to_string())
+}
+
+fn points_value(points: &[Point]) -> String {
+    let points: Vec<String> = points.iter().map(|point| format!("{},{}", point.x, point.y)).collect();
+    points.join(" ")
+}
+
+fn orientation_name(orientation: Orientation) -> &'static str {
+    match orientation {
+        Orientation::Orthogonal => "orthogonal",
+        Orientation::Isometric => "isometric",
+        Orientation::Staggered => "staggered",
+        Orientation::Hexagonal => "hexagonal",
+    }
+}
+
+fn render_order_name(render_order: RenderOrder) -> &'static str {
+    match render_order {
+        RenderOrder::RightDown => "right-down",
+        RenderOrder::RightUp => "right-up",
+        RenderOrder::LeftDown => "left-down",
+        RenderOrder::LeftUp => "left-up",
+    }
+}
+
+fn object_alignment_name(alignment: ObjectAlignment) -> &'static str {
+    match alignment {
+        ObjectAlignment::Unspecified => "unspecified",
+        ObjectAlignment::TopLeft => "topleft",
+        ObjectAlignment::Top => "top",
+        ObjectAlignment::TopRight => "topright",
+        ObjectAlignment::Left => "left",
+        ObjectAlignment::Center => "center",
+        ObjectAlignment::Right => "right",
+        ObjectAlignment::BottomLeft => "bottomleft",
+        ObjectAlignment::Bottom => "bottom",
+        ObjectAlignment::BottomRight => "bottomright",
+    }
+}
+
+fn wang_set_type_name(kind: WangSetType) -> &'static str {
+    match kind {
+        WangSetType::Corner => "corner",
+        WangSetType::Edge => "edge",
+        WangSetType::Mixed => "mixed",
+    }
+}
+
+fn halign_name(halign: HAlign) -> &'static str {
+    match halign {
+        HAlign::Left => "left",
+        HAlign::Center => "center",
+        HAlign::Right => "right",
+        HAlign::Justify => "justify",
+    }
+}
+
+fn valign_name(valign: VAlign) -> &'static str {
+    match valign {
+        VAlign::Top => "top",
+        VAlign::Center => "center",
+        VAlign::Bottom => "bottom",
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use ::utils::{asset_path, shipped_maps};
+    use super::super::{Color, TilesetCache};
+    use super::super::fixtures::parse_tmx;
+
+    use std::env;
+    use std::fs;
+
+    fn round_trip(file_name: &Path, encoding: DataEncoding) {
+        let original = TmxContent::load(file_name).unwrap();
+
+        let mut buffer = Vec::new();
+        original.write(&mut buffer, encoding).unwrap();
+
+        let base_dir = file_name.parent().unwrap();
+        let written = super::super::parse_tmx(&buffer[..], base_dir, &mut TilesetCache::new()).unwrap();
+        assert_eq!(written, original, "{} did not survive a round trip", file_name.display());
+    }
+
+    #[test]
+    fn round_trip_shipped_maps_as_csv() {
+        for file_name in shipped_maps() {
+            round_trip(&file_name, DataEncoding::Csv);
+        }
+    }
+
+    #[test]
+    fn round_trip_shipped_maps_as_base64_zlib() {
+        for file_name in shipped_maps() {
+            round_trip(&file_name, DataEncoding::Base64Zlib);
+        }
+    }
+
+    #[test]
+    fn round_trip_layer_ids_and_object_classes() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" tiledversion="1.10.2" orientation="orthogonal" renderorder="right-down" width="2" height="2" tilewidth="16" tileheight="16" infinite="0" nextlayerid="4" nextobjectid="3">
+ <layer id="1" name="Ground" width="2" height="2">
+  <data encoding="csv">0,0,0,0</data>
+ </layer>
+ <group id="2" name="Town">
+  <objectgroup id="3" name="Objects">
+   <object id="1" class="Door" x="0" y="0" width="16" height="16"/>
+   <object id="2" type="Chest" x="16" y="0" width="16" height="16"/>
+  </objectgroup>
+ </group>
+</map>"#;
+        let original = parse_tmx(xml.as_bytes()).unwrap();
+        assert_eq!(original.map.next_layer_id, Some(4));
+        assert_eq!(original.get_layer("Ground").unwrap().common.id, Some(1));
+
+        let mut buffer = Vec::new();
+        original.write(&mut buffer, DataEncoding::Csv).unwrap();
+        let text = String::from_utf8(buffer).unwrap();
+        assert!(text.contains("nextlayerid=\"4\""));
+        assert!(text.contains("<group id=\"2\""));
+        assert!(text.contains("class=\"Door\""));
+        assert!(text.contains("type=\"Chest\""));
+
+        let written = parse_tmx(text.as_bytes()).unwrap();
+        assert_eq!(written, original);
+        let objects = &written.get_object_group("Objects").unwrap().objects;
+        assert_eq!(objects.get(&1).unwrap().class_attribute, ClassAttribute::Class);
+        assert_eq!(objects.get(&2).unwrap().class_attribute, ClassAttribute::Type);
+        assert_eq!(written.get_object_group("Objects").unwrap().common.id, Some(3));
+    }
+
+    #[test]
+    fn write_data_encodings() {
+        let content = TmxContent::load(asset_path("maps/town.tmx")).unwrap();
+
+        let mut csv = Vec::new();
+        content.write(&mut csv, DataEncoding::Csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(csv.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>"));
+        assert!(csv.contains("<data encoding=\"csv\">"));
+
+        let mut zlib = Vec::new();
+        content.write(&mut zlib, DataEncoding::Base64Zlib).unwrap();
+        let zlib = String::from_utf8(zlib).unwrap();
+        assert!(zlib.contains("<data encoding=\"base64\" compression=\"zlib\">"));
+        assert!(zlib.len() < csv.len());
+    }
+
+    #[test]
+    fn round_trip_written_tsx() {
+        let content = TmxContent::load(asset_path("maps/town.tmx")).unwrap();
+        let tileset = content.get_tileset("Light").unwrap();
+        assert_eq!(tileset.image.as_ref().unwrap().trans, Some(Color::new(255, 255, 255, 255)));
+
+        let dir = env::temp_dir().join(format!("bb2-tmx-writer-{}", ::std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        tileset.save_tsx(dir.join("light.tsx")).unwrap();
+
+        let mut cache = TilesetCache::new();
+        let loaded = cache.load(&dir, "light.tsx", tileset.firstgid).unwrap();
+        assert_eq!(loaded.source, Some("light.tsx".to_string()));
+        assert_eq!(TmxTileset { source: None, ..loaded }, *tileset);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+}
@@ -0,0 +1,241 @@
+use std::f32;
+
+use super::noisy_float::prelude::*;
+
+use super::{Area, Vector2};
+
+/// The moves `move_and_slide` makes at most; one per wall it slides along
+/// plus the last, which is enough for any corner.
+const MAX_SLIDES: usize = 4;
+
+/// Where a moving area first touches an obstacle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hit {
+    /// The fraction of the motion done at the contact, from 0 to 1.
+    pub time: R32,
+    /// Points out of the obstacle's side that was hit.
+    pub normal: Vector2,
+}
+
+/// The result of `Area::move_and_slide`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Slide {
+    pub area: Area,
+    /// The obstacles run into, by index, in the order they were hit.
+    pub hits: Vec<(usize, Hit)>,
+}
+
+impl Area {
+
+    /// Moves this area along `motion` and returns where it first touches
+    /// `obstacle`, however fast it moves. Like `collision`, touching edges
+    /// do not count, so an area flush with a wall can slide along it; an
+    /// obstacle the area already overlaps is ignored so it can move out.
+    pub fn sweep(&self, motion: Vector2, obstacle: &Area) -> Option<Hit> {
+        let (entry_x, exit_x) = axis_times(self.left, self.right, obstacle.left, obstacle.right, motion.x.raw())?;
+        let (entry_y, exit_y) = axis_times(self.top, self.bottom, obstacle.top, obstacle.bottom, motion.y.raw())?;
+
+        let entry = entry_x.max(entry_y);
+        let exit = exit_x.min(exit_y);
+        if entry >= exit || !(0.0 ..= 1.0).contains(&entry) {
+            return None;
+        }
+
+        let normal = if entry_x >= entry_y {
+            Vector2::new(-r32(motion.x.raw().signum()), r32(0.0))
+        } else {
+            Vector2::new(r32(0.0), -r32(motion.y.raw().signum()))
+        };
+
+        Some(Hit {
+            time: r32(entry),
+            normal,
+        })
+    }
+
+    /// The first of `obstacles` hit when moving along `motion`, with its index.
+    pub fn sweep_all(&self, motion: Vector2, obstacles: &[Area]) -> Option<(usize, Hit)> {
+        obstacles.iter()
+            .enumerate()
+            .filter_map(|(idx, obstacle)| self.sweep(motion, obstacle).map(|hit| (idx, hit)))
+            .min_by_key(|&(_, hit)| hit.time)
+    }
+
+    /// Moves this area along `motion`, stopping at obstacles and sliding
+    /// along them with what is left of the motion.
+    pub fn move_and_slide(&self, motion: Vector2, obstacles: &[Area]) -> Slide {
+        let mut area = self.clone();
+        let mut remaining = motion;
+        let mut hits = Vec::new();
+
+        for _ in 0 .. MAX_SLIDES {
+            if remaining == Vector2::zero() {
+                break;
+            }
+            let (idx, hit) = match area.sweep_all(remaining, obstacles) {
+                Some(contact) => contact,
+                None => {
+                    area = area.translate(remaining);
+                    break;
+                }
+            };
+
+            area = flush(&area.translate(remaining * hit.time), &obstacles[idx], hit.normal);
+
+            let left = remaining * (r32(1.0) - hit.time);
+            remaining = left - hit.normal * left.dot(&hit.normal);
+            hits.push((idx, hit));
+        }
+
+        Slide { area, hits }
+    }
+
+}
+
+/// When the area's `[min, max]` starts and stops overlapping the obstacle's
+/// along one axis, in fractions of `motion`.
+fn axis_times(min: R32, max: R32, obstacle_min: R32, obstacle_max: R32, motion: f32) -> Option<(f32, f32)> {
+    let (min, max, obstacle_min, obstacle_max) = (min.raw(), max.raw(), obstacle_min.raw(), obstacle_max.raw());
+
+    if motion > 0.0 {
+        Some(((obstacle_min - max) / motion, (obstacle_max - min) / motion))
+    } else if motion < 0.0 {
+        Some(((obstacle_max - min) / motion, (obstacle_min - max) / motion))
+    } else if max > obstacle_min && min < obstacle_max {
+        Some((f32::NEG_INFINITY, f32::INFINITY))
+    } else {
+        None
+    }
+}
+
+/// Places `area` exactly against the side of `obstacle` that `normal` points
+/// out of, so rounding cannot leave it overlapping the obstacle.
+fn flush(area: &Area, obstacle: &Area, normal: Vector2) -> Area {
+    let zero = r32(0.0);
+    let x = if normal.x < zero {
+        obstacle.left - area.width
+    } else if normal.x > zero {
+        obstacle.right
+    } else {
+        area.x
+    };
+    let y = if normal.y < zero {
+        obstacle.top - area.height
+    } else if normal.y > zero {
+        obstacle.bottom
+    } else {
+        area.y
+    };
+    Area::new(x, y, area.width, area.height)
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use ::utils::asset_path;
+    use ::proptest::prelude::*;
+    use ::utils::tmx::{ObjectShape, TmxContent};
+
+    fn area(x: f32, y: f32, width: f32, height: f32) -> Area {
+        Area::new(r32(x), r32(y), r32(width), r32(height))
+    }
+
+    fn v(x: f32, y: f32) -> Vector2 {
+        Vector2::new(r32(x), r32(y))
+    }
+
+    #[test]
+    fn sweep_hits_thin_walls() {
+        let player = area(0.0, 0.0, 16.0, 16.0);
+        let wall = area(40.0, -10.0, 8.0, 50.0);
+
+        assert!(!player.translate(v(100.0, 0.0)).collision(&wall));
+        assert_eq!(player.sweep(v(100.0, 0.0), &wall), Some(Hit { time: r32(0.24), normal: v(-1.0, 0.0) }));
+        assert_eq!(player.sweep(v(10.0, 0.0), &wall), None);
+        assert_eq!(player.sweep(v(-100.0, 0.0), &wall), None);
+
+        let ceiling = area(-50.0, -20.0, 100.0, 8.0);
+        assert_eq!(player.sweep(v(0.0, -24.0), &ceiling), Some(Hit { time: r32(0.5), normal: v(0.0, 1.0) }));
+    }
+
+    #[test]
+    fn sweep_ignores_touching_and_overlapping() {
+        let player = area(0.0, 0.0, 16.0, 16.0);
+
+        let floor = area(-50.0, 16.0, 100.0, 8.0);
+        assert_eq!(player.sweep(v(30.0, 0.0), &floor), None);
+        assert_eq!(player.sweep(v(30.0, 1.0), &floor), Some(Hit { time: r32(0.0), normal: v(0.0, -1.0) }));
+
+        let corner = area(16.0, 16.0, 8.0, 8.0);
+        assert_eq!(player.sweep(v(10.0, -10.0), &corner), None);
+
+        let inside = area(4.0, 4.0, 4.0, 4.0);
+        assert_eq!(player.sweep(v(10.0, 10.0), &inside), None);
+    }
+
+    #[test]
+    fn sweep_all_finds_the_first_hit() {
+        let player = area(0.0, 0.0, 16.0, 16.0);
+        let walls = vec![area(80.0, 0.0, 8.0, 16.0), area(40.0, 0.0, 8.0, 16.0), area(0.0, 40.0, 16.0, 8.0)];
+        assert_eq!(player.sweep_all(v(100.0, 0.0), &walls), Some((1, Hit { time: r32(0.24), normal: v(-1.0, 0.0) })));
+        assert_eq!(player.sweep_all(v(0.0, -100.0), &walls), None);
+    }
+
+    #[test]
+    fn move_and_slide_along_walls() {
+        let player = area(0.0, 0.0, 16.0, 16.0);
+        let walls = vec![area(40.0, -100.0, 8.0, 200.0), area(-100.0, 40.0, 200.0, 8.0)];
+
+        let slide = player.move_and_slide(v(100.0, 50.0), &walls[.. 1]);
+        assert_eq!(slide.area, area(24.0, 50.0, 16.0, 16.0));
+        assert_eq!(slide.hits.len(), 1);
+
+        let slide = player.move_and_slide(v(100.0, 100.0), &walls);
+        assert_eq!(slide.area, area(24.0, 24.0, 16.0, 16.0));
+        assert_eq!(slide.hits.iter().map(|&(idx, _)| idx).collect::<Vec<_>>(), vec![0, 1]);
+
+        assert_eq!(player.move_and_slide(v(3.0, -2.0), &[]).area, area(3.0, -2.0, 16.0, 16.0));
+    }
+
+    #[test]
+    fn move_and_slide_in_topworld() {
+        let topworld = TmxContent::load(asset_path("maps/topworld.tmx")).unwrap();
+        let mut objects: Vec<_> = topworld.get_object_group("MAP_COLLISION_LAYER").unwrap().objects.values().collect();
+        objects.sort_by_key(|object| object.id);
+        let walls: Vec<Area> = objects.iter()
+            .filter(|object| object.shape == ObjectShape::Rectangle)
+            .map(|object| object.area.clone())
+            .collect();
+
+        // the west wall of the map is 16 pixels wide at x = 112
+        let player = area(60.0, 400.0, 16.0, 16.0);
+        let slide = player.move_and_slide(v(200.0, 50.0), &walls);
+        assert_eq!(slide.area, area(96.0, 450.0, 16.0, 16.0));
+        assert!(walls.iter().all(|wall| !slide.area.collision(wall)));
+    }
+
+    fn arb_area() -> impl Strategy<Value = Area> {
+        (-40i32..40, -40i32..40, 1i32..20, 1i32..20).prop_map(|(x, y, width, height)| {
+            area(x as f32, y as f32, width as f32, height as f32)
+        })
+    }
+
+    proptest! {
+
+        #[test]
+        fn slides_never_end_inside_obstacles(player in arb_area(), walls in prop::collection::vec(arb_area(), 0..6), dx in -80i32..80, dy in -80i32..80) {
+            let walls: Vec<Area> = walls.into_iter().filter(|wall| !player.collision(wall)).collect();
+            let slide = player.move_and_slide(v(dx as f32, dy as f32), &walls);
+            prop_assert_eq!(slide.area.size(), player.size());
+            for wall in &walls {
+                prop_assert!(!slide.area.collision(wall), "{:?} ends inside {:?}", slide.area, wall);
+            }
+            if slide.hits.is_empty() {
+                prop_assert_eq!(slide.area, player.translate(v(dx as f32, dy as f32)));
+            }
+        }
+
+    }
+
+}
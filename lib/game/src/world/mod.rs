@@ -1,8 +1,15 @@
 
 extern crate noisy_float;
 
+mod collision;
+pub mod schema;
+mod vector;
+
 use self::noisy_float::prelude::*;
 
+pub use self::collision::{Hit, Slide};
+pub use self::vector::{Point, Vector2};
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Area {
     pub x: R32,
@@ -15,8 +22,6 @@ pub struct Area {
     pub bottom: R32,
 }
 
-pub struct Point(R32, R32);
-
 impl Area {
 
     pub fn new(x: R32, y: R32, width: R32, height: R32) -> Area {
@@ -29,28 +34,60 @@ impl Area {
         }
     }
 
+    /// The area spanned by two opposite corners, in any order.
+    pub fn from_corners(a: Point, b: Point) -> Area {
+        let top_left = Point::new(a.x.min(b.x), a.y.min(b.y));
+        let bottom_right = Point::new(a.x.max(b.x), a.y.max(b.y));
+        Area::from_position(top_left, bottom_right - top_left)
+    }
+
+    pub fn from_position(position: Point, size: Vector2) -> Area {
+        Area::new(position.x, position.y, size.x, size.y)
+    }
+
+    /// The top left corner.
+    pub fn position(&self) -> Point {
+        Point::new(self.x, self.y)
+    }
+
+    pub fn size(&self) -> Vector2 {
+        Vector2::new(self.width, self.height)
+    }
+
+    pub fn center(&self) -> Point {
+        self.position() + self.size() / r32(2.0)
+    }
+
+    pub fn top_left(&self) -> Point {
+        Point::new(self.left, self.top)
+    }
+
+    pub fn top_right(&self) -> Point {
+        Point::new(self.right, self.top)
+    }
+
+    pub fn bottom_left(&self) -> Point {
+        Point::new(self.left, self.bottom)
+    }
+
+    pub fn bottom_right(&self) -> Point {
+        Point::new(self.right, self.bottom)
+    }
+
     pub fn area(&self) -> R32 {
         return self.width * self.height;
     }
 
+    /// The overlap of both areas. Areas that only touch intersect in an
+    /// area of width or height 0; use `collision` to ignore those.
     pub fn intersect(&self, other: &Area) -> Option<Area> {
-        println!("{:?}\n{:?}\n", self, other);
-
-        if  self.contains(&Point(other.top, other.left)) || 
-            self.contains(&Point(other.top, other.right)) || 
-            self.contains(&Point(other.bottom, other.left)) || 
-            self.contains(&Point(other.bottom, other.right))
-        {
-            let left = self.left.max(other.left);
-            let top = self.top.max(other.top);
-            let right = self.right.min(other.right);
-            let bottom = self.bottom.min(other.bottom);
-            let x = left;
-            let y = top;
-            let width = right - left;
-            let height = bottom - top;
-
-            Some(Area::new(x, y, width, height))
+        let left = self.left.max(other.left);
+        let top = self.top.max(other.top);
+        let right = self.right.min(other.right);
+        let bottom = self.bottom.min(other.bottom);
+
+        if left <= right && top <= bottom {
+            Some(Area::new(left, top, right - left, bottom - top))
         } else {
             None
         }
@@ -63,8 +100,58 @@ impl Area {
         }
     }
 
+    /// The smallest area containing both.
+    pub fn union(&self, other: &Area) -> Area {
+        let left = self.left.min(other.left);
+        let top = self.top.min(other.top);
+        let right = self.right.max(other.right);
+        let bottom = self.bottom.max(other.bottom);
+        Area::new(left, top, right - left, bottom - top)
+    }
+
+    /// The parts of this area `other` does not cover, as up to four areas
+    /// that do not overlap: the full width above and below `other`, then
+    /// what is left and right of it.
+    pub fn difference(&self, other: &Area) -> Vec<Area> {
+        let overlap = match self.intersect(other) {
+            Some(ref overlap) if overlap.area() > r32(0.0) => overlap.clone(),
+            _ => return vec![self.clone()],
+        };
+
+        let parts = vec![
+            Area::new(self.left, self.top, self.width, overlap.top - self.top),
+            Area::new(self.left, overlap.bottom, self.width, self.bottom - overlap.bottom),
+            Area::new(self.left, overlap.top, overlap.left - self.left, overlap.height),
+            Area::new(overlap.right, overlap.top, self.right - overlap.right, overlap.height),
+        ];
+
+        parts.into_iter().filter(|part| part.area() > r32(0.0)).collect()
+    }
+
     pub fn contains(&self, point: &Point) -> bool {
-        return point.0 >= self.left && point.0 <= self.right && point.1 >= self.top && point.1 <= self.bottom
+        return point.x >= self.left && point.x <= self.right && point.y >= self.top && point.y <= self.bottom
+    }
+
+    /// Whether `other` lies completely inside this area; shared edges count as inside.
+    pub fn contains_area(&self, other: &Area) -> bool {
+        other.left >= self.left && other.right <= self.right && other.top >= self.top && other.bottom <= self.bottom
+    }
+
+    /// The shortest distance between the edges of both areas, 0 if they touch or overlap.
+    pub fn distance_to(&self, other: &Area) -> R32 {
+        let zero = r32(0.0);
+        let dx = (other.left - self.right).max(self.left - other.right).max(zero);
+        let dy = (other.top - self.bottom).max(self.top - other.bottom).max(zero);
+        (dx * dx + dy * dy).sqrt()
+    }
+
+    /// The point of this area closest to `point`.
+    pub fn clamp_point(&self, point: &Point) -> Point {
+        Point::new(point.x.max(self.left).min(self.right), point.y.max(self.top).min(self.bottom))
+    }
+
+    pub fn translate(&self, offset: Vector2) -> Area {
+        Area::from_position(self.position() + offset, self.size())
     }
 
     pub fn enlarge(&self, value: R32) -> Area {
@@ -93,6 +180,7 @@ impl Area {
 mod test {
 
     use super::*;
+    use ::proptest::prelude::*;
 
     #[test]
     fn area_new() {
@@ -117,31 +205,31 @@ mod test {
     fn area_contains_point() {
         let area = Area::new(r32(4.0), r32(5.0), r32(3.0), r32(8.0));
         
-        let p = Point(r32(1.0), r32(1.0));
+        let p = Point::new(r32(1.0), r32(1.0));
         assert!(!area.contains(&p));
         
-        let p = Point(r32(5.0), r32(1.0));
+        let p = Point::new(r32(5.0), r32(1.0));
         assert!(!area.contains(&p));
         
-        let p = Point(r32(11.0), r32(1.0));
+        let p = Point::new(r32(11.0), r32(1.0));
         assert!(!area.contains(&p));
         
-        let p = Point(r32(1.0), r32(6.0));
+        let p = Point::new(r32(1.0), r32(6.0));
         assert!(!area.contains(&p));
         
-        let p = Point(r32(5.0), r32(6.0));
+        let p = Point::new(r32(5.0), r32(6.0));
         assert!(area.contains(&p));
         
-        let p = Point(r32(11.0), r32(6.0));
+        let p = Point::new(r32(11.0), r32(6.0));
         assert!(!area.contains(&p));
         
-        let p = Point(r32(1.0), r32(14.0));
+        let p = Point::new(r32(1.0), r32(14.0));
         assert!(!area.contains(&p));
         
-        let p = Point(r32(5.0), r32(14.0));
+        let p = Point::new(r32(5.0), r32(14.0));
         assert!(!area.contains(&p));
         
-        let p = Point(r32(11.0), r32(14.0));
+        let p = Point::new(r32(11.0), r32(14.0));
         assert!(!area.contains(&p));
     }
 
@@ -156,6 +244,93 @@ mod test {
 
         let other = Area::new(r32(3.0), r32(4.0), r32(3.0), r32(8.0));
         assert_eq!(area.collision(&other), true);
+
+        let other = Area::new(r32(7.0), r32(5.0), r32(3.0), r32(8.0));
+        assert!(!area.collision(&other));
+    }
+
+    #[test]
+    fn area_intersect_cross() {
+        let wide = Area::new(r32(0.0), r32(4.0), r32(12.0), r32(2.0));
+        let tall = Area::new(r32(5.0), r32(0.0), r32(2.0), r32(10.0));
+        let expected = Area::new(r32(5.0), r32(4.0), r32(2.0), r32(2.0));
+        assert_eq!(wide.intersect(&tall), Some(expected.clone()));
+        assert_eq!(tall.intersect(&wide), Some(expected));
+
+        let touching = Area::new(r32(12.0), r32(0.0), r32(2.0), r32(10.0));
+        assert_eq!(wide.intersect(&touching), Some(Area::new(r32(12.0), r32(4.0), r32(0.0), r32(2.0))));
+        assert!(!wide.collision(&touching));
+        assert_eq!(wide.intersect(&touching.translate(Vector2::new(r32(1.0), r32(0.0)))), None);
+    }
+
+    #[test]
+    fn area_union() {
+        let area = Area::new(r32(4.0), r32(5.0), r32(3.0), r32(8.0));
+        let other = Area::new(r32(10.0), r32(1.0), r32(2.0), r32(2.0));
+        assert_eq!(area.union(&other), Area::new(r32(4.0), r32(1.0), r32(8.0), r32(12.0)));
+    }
+
+    #[test]
+    fn area_difference() {
+        let area = Area::new(r32(0.0), r32(0.0), r32(10.0), r32(10.0));
+
+        let hole = Area::new(r32(2.0), r32(3.0), r32(4.0), r32(5.0));
+        assert_eq!(area.difference(&hole), vec![
+            Area::new(r32(0.0), r32(0.0), r32(10.0), r32(3.0)),
+            Area::new(r32(0.0), r32(8.0), r32(10.0), r32(2.0)),
+            Area::new(r32(0.0), r32(3.0), r32(2.0), r32(5.0)),
+            Area::new(r32(6.0), r32(3.0), r32(4.0), r32(5.0)),
+        ]);
+
+        let left_half = Area::new(-r32(5.0), -r32(5.0), r32(10.0), r32(20.0));
+        assert_eq!(area.difference(&left_half), vec![Area::new(r32(5.0), r32(0.0), r32(5.0), r32(10.0))]);
+
+        assert_eq!(area.difference(&area.enlarge(r32(1.0))), vec![]);
+        assert_eq!(area.difference(&hole.translate(Vector2::new(r32(20.0), r32(0.0)))), vec![area.clone()]);
+    }
+
+    #[test]
+    fn area_contains_area() {
+        let area = Area::new(r32(4.0), r32(5.0), r32(3.0), r32(8.0));
+        assert!(area.contains_area(&area));
+        assert!(area.contains_area(&area.shrink(r32(1.0))));
+        assert!(!area.contains_area(&area.enlarge(r32(1.0))));
+        assert!(!area.contains_area(&area.translate(Vector2::new(r32(1.0), r32(0.0)))));
+    }
+
+    #[test]
+    fn area_distance_to() {
+        let area = Area::new(r32(0.0), r32(0.0), r32(2.0), r32(2.0));
+        assert_eq!(area.distance_to(&Area::new(r32(5.0), r32(0.0), r32(1.0), r32(1.0))), r32(3.0));
+        assert_eq!(area.distance_to(&Area::new(r32(5.0), r32(6.0), r32(1.0), r32(1.0))), r32(5.0));
+        assert_eq!(area.distance_to(&Area::new(r32(1.0), r32(1.0), r32(5.0), r32(5.0))), r32(0.0));
+        assert_eq!(area.distance_to(&Area::new(r32(2.0), r32(0.0), r32(1.0), r32(1.0))), r32(0.0));
+    }
+
+    #[test]
+    fn area_clamp_point() {
+        let area = Area::new(r32(4.0), r32(5.0), r32(3.0), r32(8.0));
+        assert_eq!(area.clamp_point(&Point::new(r32(5.0), r32(6.0))), Point::new(r32(5.0), r32(6.0)));
+        assert_eq!(area.clamp_point(&Point::new(r32(1.0), r32(20.0))), Point::new(r32(4.0), r32(13.0)));
+        assert_eq!(area.clamp_point(&Point::new(r32(9.0), r32(1.0))), Point::new(r32(7.0), r32(5.0)));
+    }
+
+    #[test]
+    fn area_corners() {
+        let area = Area::new(r32(4.0), r32(5.0), r32(3.0), r32(8.0));
+        assert_eq!(area.position(), Point::new(r32(4.0), r32(5.0)));
+        assert_eq!(area.size(), Vector2::new(r32(3.0), r32(8.0)));
+        assert_eq!(area.center(), Point::new(r32(5.5), r32(9.0)));
+        assert_eq!(area.top_right(), Point::new(r32(7.0), r32(5.0)));
+        assert_eq!(area.bottom_left(), Point::new(r32(4.0), r32(13.0)));
+        assert_eq!(Area::from_corners(area.bottom_right(), area.top_left()), area);
+        assert_eq!(Area::from_corners(area.top_right(), area.bottom_left()), area);
+    }
+
+    #[test]
+    fn area_translate() {
+        let area = Area::new(r32(4.0), r32(5.0), r32(3.0), r32(8.0));
+        assert_eq!(area.translate(Vector2::new(-r32(4.0), r32(1.5))), Area::new(r32(0.0), r32(6.5), r32(3.0), r32(8.0)));
     }
 
     #[test]
@@ -186,4 +361,80 @@ mod test {
         let expected = Area::new(r32(0.0), -r32(3.0), r32(8.0), r32(16.0));
         assert_eq!(scaled, expected);
     }
+
+    // whole coordinates keep every sum and product exact in an f32
+    fn arb_area() -> impl Strategy<Value = Area> {
+        (-20i32..20, -20i32..20, 0i32..15, 0i32..15).prop_map(|(x, y, width, height)| {
+            Area::new(r32(x as f32), r32(y as f32), r32(width as f32), r32(height as f32))
+        })
+    }
+
+    fn arb_point() -> impl Strategy<Value = Point> {
+        (-40i32..40, -40i32..40).prop_map(|(x, y)| Point::new(r32(x as f32), r32(y as f32)))
+    }
+
+    proptest! {
+
+        #[test]
+        fn intersection_lies_in_both(a in arb_area(), b in arb_area()) {
+            prop_assert_eq!(a.intersect(&b), b.intersect(&a));
+            if let Some(overlap) = a.intersect(&b) {
+                prop_assert!(a.contains_area(&overlap) && b.contains_area(&overlap));
+            }
+            prop_assert_eq!(a.collision(&b), a.intersect(&b).is_some_and(|overlap| overlap.area() > r32(0.0)));
+            prop_assert_eq!(a.contains_area(&b), a.intersect(&b) == Some(b.clone()));
+        }
+
+        #[test]
+        fn union_contains_both(a in arb_area(), b in arb_area()) {
+            let union = a.union(&b);
+            prop_assert_eq!(&union, &b.union(&a));
+            prop_assert!(union.contains_area(&a) && union.contains_area(&b));
+        }
+
+        #[test]
+        fn difference_and_intersection_partition_the_area(a in arb_area(), b in arb_area()) {
+            let parts = a.difference(&b);
+            prop_assert!(parts.len() <= 4);
+
+            let mut total = a.intersect(&b).map(|overlap| overlap.area()).unwrap_or(r32(0.0));
+            for (idx, part) in parts.iter().enumerate() {
+                prop_assert!(a.contains_area(part));
+                prop_assert!(!part.collision(&b));
+                for other in &parts[idx + 1 ..] {
+                    prop_assert!(!part.collision(other));
+                }
+                total += part.area();
+            }
+            prop_assert_eq!(total, a.area());
+        }
+
+        #[test]
+        fn distance_is_zero_when_touching(a in arb_area(), b in arb_area()) {
+            prop_assert_eq!(a.distance_to(&b), b.distance_to(&a));
+            prop_assert_eq!(a.distance_to(&b) == r32(0.0), a.intersect(&b).is_some());
+            prop_assert!(a.union(&b).distance_to(&a) == r32(0.0));
+        }
+
+        #[test]
+        fn clamped_points_are_inside(a in arb_area(), point in arb_point()) {
+            let clamped = a.clamp_point(&point);
+            prop_assert!(a.contains(&clamped));
+            if a.contains(&point) {
+                prop_assert_eq!(clamped, point);
+            }
+            let dot = Area::from_position(point, Vector2::zero());
+            prop_assert_eq!(a.distance_to(&dot), point.distance_to(&clamped));
+        }
+
+        #[test]
+        fn translation_commutes_with_intersection(a in arb_area(), b in arb_area(), dx in -10i32..10, dy in -10i32..10) {
+            let offset = Vector2::new(r32(dx as f32), r32(dy as f32));
+            let moved = a.translate(offset);
+            prop_assert_eq!(moved.size(), a.size());
+            prop_assert_eq!(moved.center(), a.center() + offset);
+            prop_assert_eq!(moved.intersect(&b.translate(offset)), a.intersect(&b).map(|overlap| overlap.translate(offset)));
+        }
+
+    }
 }
\ No newline at end of file
@@ -0,0 +1,356 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use ::utils::tmx::{Diagnostic, Object, PropertyEnum, Severity, TmxContent, TmxError};
+
+/// What a `SchemaDiagnostic` was raised by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SchemaCheck {
+    /// A layer every map needs is missing.
+    RequiredLayer,
+    /// An object lacks a name or property its layer's schema asks for.
+    ObjectSchema,
+    MissingSpawn,
+    UnknownMap,
+    UnknownZone,
+    /// A quest that is not in `assets/quests`.
+    UnknownQuest,
+    /// A task that is not part of its quest.
+    UnknownTask,
+}
+
+/// A broken gameplay convention `MapSchema::check` found; `subject` names the layer.
+pub type SchemaDiagnostic = Diagnostic<SchemaCheck>;
+
+/// What an object name or property value has to refer to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reference {
+    /// Anything goes.
+    Any,
+    /// A map in `assets/maps`, like `TOP_WORLD` for `topworld.tmx`.
+    Map,
+    /// A `zoneID` from `scripts/monster_zones.json`.
+    Zone,
+    /// A `questID` from `assets/quests`.
+    Quest,
+    /// A task of the quest the object's name refers to.
+    Task,
+}
+
+/// The conventions for the objects of one `MAP_*` object group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayerSchema {
+    pub layer: String,
+    /// Whether every map needs this layer.
+    pub required: bool,
+    /// What object names refer to; `None` if objects are not named.
+    pub names: Option<Reference>,
+    /// Object names at least one object of the layer must have.
+    pub required_names: Vec<String>,
+    /// Properties every object of the layer must have.
+    pub properties: Vec<(String, Reference)>,
+}
+
+impl LayerSchema {
+
+    pub fn new(layer: &str) -> LayerSchema {
+        LayerSchema {
+            layer: layer.to_string(),
+            required: false,
+            names: None,
+            required_names: Vec::new(),
+            properties: Vec::new(),
+        }
+    }
+
+    pub fn required(mut self) -> LayerSchema {
+        self.required = true;
+        self
+    }
+
+    pub fn names(mut self, reference: Reference) -> LayerSchema {
+        self.names = Some(reference);
+        self
+    }
+
+    pub fn require_name(mut self, name: &str) -> LayerSchema {
+        self.required_names.push(name.to_string());
+        self
+    }
+
+    pub fn property(mut self, name: &str, reference: Reference) -> LayerSchema {
+        self.properties.push((name.to_string(), reference));
+        self
+    }
+
+}
+
+/// The object layers the game reads from a map and what they may refer to.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MapSchema {
+    pub layers: Vec<LayerSchema>,
+    /// Map file stems, upper-cased and without underscores; see `add_map`.
+    pub maps: HashSet<String>,
+    pub zones: HashSet<String>,
+    /// Task ids by quest id.
+    pub quests: HashMap<String, HashSet<String>>,
+}
+
+impl MapSchema {
+
+    /// The layers of the game's maps, without maps, zones and quests; see `load`.
+    pub fn standard() -> MapSchema {
+        MapSchema {
+            layers: vec![
+                LayerSchema::new("MAP_COLLISION_LAYER").required(),
+                LayerSchema::new("MAP_SPAWNS_LAYER").required().names(Reference::Any).require_name("START"),
+                LayerSchema::new("MAP_PORTAL_LAYER").required().names(Reference::Map),
+                LayerSchema::new("MAP_ENEMY_SPAWN_LAYER").names(Reference::Zone),
+                LayerSchema::new("MAP_QUEST_ITEM_SPAWN_LAYER").names(Reference::Quest).property("taskID", Reference::Task),
+                LayerSchema::new("MAP_QUEST_DISCOVER_LAYER").names(Reference::Quest).property("taskID", Reference::Task),
+            ],
+            maps: HashSet::new(),
+            zones: HashSet::new(),
+            quests: HashMap::new(),
+        }
+    }
+
+    /// The standard schema with the maps, zones and quests found in `assets_dir`.
+    pub fn load<P: AsRef<Path>>(assets_dir: P) -> Result<MapSchema, TmxError> {
+        let assets_dir = assets_dir.as_ref();
+        let mut schema = MapSchema::standard();
+
+        for entry in fs::read_dir(assets_dir.join("maps"))? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|extension| extension == "tmx") {
+                if let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    schema.add_map(stem);
+                }
+            }
+        }
+
+        schema.zones = read_zones(&fs::read_to_string(assets_dir.join("scripts/monster_zones.json"))?);
+
+        let mut quest_files: Vec<_> = fs::read_dir(assets_dir.join("quests"))?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<_, _>>()?;
+        quest_files.sort();
+        for path in quest_files {
+            if path.extension().is_some_and(|extension| extension == "json") {
+                if let Some((quest, tasks)) = read_quest(&fs::read_to_string(&path)?) {
+                    schema.quests.insert(quest, tasks);
+                }
+            }
+        }
+
+        Ok(schema)
+    }
+
+    /// Adds a map by file stem or by the name portals use for it, which may
+    /// differ in case and underscores: `topworld` is `TOP_WORLD`.
+    pub fn add_map(&mut self, name: &str) {
+        self.maps.insert(map_key(name));
+    }
+
+    pub fn knows_map(&self, name: &str) -> bool {
+        self.maps.contains(&map_key(name))
+    }
+
+    /// Checks the object layers of `content` against the schema.
+    pub fn check(&self, content: &TmxContent) -> Vec<SchemaDiagnostic> {
+        let mut diagnostics = Vec::new();
+
+        for layer in &self.layers {
+            let groups: Vec<_> = content.get_all(&layer.layer).filter_map(|entry| entry.as_object_group()).collect();
+            if groups.is_empty() {
+                if layer.required {
+                    diagnostics.push(diagnostic(Severity::Error, SchemaCheck::RequiredLayer, &layer.layer, "the map has no such object group".to_string()));
+                }
+                continue;
+            }
+
+            let mut objects: Vec<&Object> = groups.iter().flat_map(|group| group.objects.values()).collect();
+            objects.sort_by_key(|object| object.id);
+
+            for name in &layer.required_names {
+                if !objects.iter().any(|object| object.name == *name) {
+                    diagnostics.push(diagnostic(Severity::Error, SchemaCheck::MissingSpawn, &layer.layer, format!("no object is named '{}'", name)));
+                }
+            }
+
+            for object in objects {
+                self.check_object(layer, object, &mut diagnostics);
+            }
+        }
+
+        diagnostics
+    }
+
+    fn check_object(&self, layer: &LayerSchema, object: &Object, diagnostics: &mut Vec<SchemaDiagnostic>) {
+        if let Some(reference) = layer.names {
+            if object.name.is_empty() {
+                diagnostics.push(diagnostic(Severity::Warning, SchemaCheck::ObjectSchema, &layer.layer, format!("object {} has no name", object.id)));
+            } else if let Some(problem) = self.refers_to(reference, &object.name, object) {
+                diagnostics.push(diagnostic(Severity::Error, problem.0, &layer.layer, format!("object {} is named '{}', {}", object.id, object.name, problem.1)));
+            }
+        }
+
+        for (property, reference) in &layer.properties {
+            match object.properties.get(property.as_str()).and_then(|property| reference_value(&property.value)) {
+                Some(value) => {
+                    if let Some(problem) = self.refers_to(*reference, &value, object) {
+                        diagnostics.push(diagnostic(Severity::Error, problem.0, &layer.layer, format!("object {} has {} '{}', {}", object.id, property, value, problem.1)));
+                    }
+                }
+                None => diagnostics.push(diagnostic(Severity::Error, SchemaCheck::ObjectSchema, &layer.layer, format!("object {} has no {}", object.id, property))),
+            }
+        }
+    }
+
+    /// What is wrong with `value` as a `reference`, if anything.
+    fn refers_to(&self, reference: Reference, value: &str, object: &Object) -> Option<(SchemaCheck, String)> {
+        match reference {
+            Reference::Any => None,
+            Reference::Map if !self.knows_map(value) => Some((SchemaCheck::UnknownMap, "which is not a known map".to_string())),
+            Reference::Zone if !self.zones.contains(value) => Some((SchemaCheck::UnknownZone, "which is not a zone in monster_zones.json".to_string())),
+            Reference::Quest if !self.quests.contains_key(value) => Some((SchemaCheck::UnknownQuest, "which is not a quest".to_string())),
+            Reference::Task => match self.quests.get(&object.name) {
+                Some(tasks) if tasks.contains(value) => None,
+                Some(_) => Some((SchemaCheck::UnknownTask, format!("which is not a task of quest '{}'", object.name))),
+                // the unknown quest is reported for the name already
+                None => None,
+            },
+            _ => None,
+        }
+    }
+
+}
+
+fn diagnostic(severity: Severity, check: SchemaCheck, layer: &str, message: String) -> SchemaDiagnostic {
+    Diagnostic {
+        severity,
+        check,
+        subject: layer.to_string(),
+        message,
+    }
+}
+
+fn map_key(name: &str) -> String {
+    name.to_uppercase().replace('_', "")
+}
+
+fn reference_value(value: &PropertyEnum) -> Option<String> {
+    match *value {
+        PropertyEnum::String(ref value) | PropertyEnum::File(ref value) => Some(value.clone()),
+        PropertyEnum::Int(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+/// The value of a `key : value` line of the game's relaxed JSON files.
+fn entry<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let mut parts = line.trim().splitn(2, ':');
+    if parts.next()?.trim() != key {
+        return None;
+    }
+    Some(parts.next()?.trim().trim_end_matches(',').trim_matches('"'))
+}
+
+/// The `zoneID`s of `monster_zones.json`. The game's data files are
+/// libGDX's relaxed JSON, which serde_json does not read, so only the
+/// `key : value` lines needed are picked out.
+fn read_zones(source: &str) -> HashSet<String> {
+    source.lines().filter_map(|line| entry(line, "zoneID")).map(str::to_string).collect()
+}
+
+/// The `questID` of a quest file and the `id`s of its tasks.
+fn read_quest(source: &str) -> Option<(String, HashSet<String>)> {
+    let quest = source.lines().filter_map(|line| entry(line, "questID")).next()?;
+    let tasks = source.lines().filter_map(|line| entry(line, "id")).map(str::to_string).collect();
+    Some((quest.to_string(), tasks))
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use ::utils::{asset_path, shipped_maps};
+
+    fn checks(diagnostics: &[SchemaDiagnostic]) -> Vec<(SchemaCheck, &str)> {
+        diagnostics.iter().map(|diagnostic| (diagnostic.check, diagnostic.message.as_str())).collect()
+    }
+
+    #[test]
+    fn read_game_data() {
+        let schema = MapSchema::load(asset_path("")).unwrap();
+        assert_eq!(schema.zones.len(), 10);
+        assert!(schema.zones.contains("7"));
+
+        let tasks = &schema.quests["1"];
+        let mut tasks: Vec<&str> = tasks.iter().map(String::as_str).collect();
+        tasks.sort();
+        assert_eq!(tasks, vec!["1", "2", "3", "4"]);
+        assert_eq!(schema.quests.len(), 3);
+
+        assert_eq!(schema.maps.len(), 3);
+        assert!(schema.knows_map("TOP_WORLD") && schema.knows_map("TOWN") && schema.knows_map("CASTLE_OF_DOOM"));
+        assert!(!schema.knows_map("DUNGEON"));
+    }
+
+    #[test]
+    fn shipped_maps_follow_the_schema() {
+        let schema = MapSchema::load(asset_path("")).unwrap();
+        for file_name in shipped_maps() {
+            let diagnostics = schema.check(&TmxContent::load(&file_name).unwrap());
+            assert!(diagnostics.is_empty(), "{}: {:?}", file_name.display(), diagnostics);
+        }
+    }
+
+    #[test]
+    fn report_broken_conventions() {
+        let xml = r#"<map version="1.0" orientation="orthogonal" width="1" height="1" tilewidth="16" tileheight="16" nextobjectid="11">
+ <objectgroup name="MAP_SPAWNS_LAYER">
+  <object id="1" name="NPC_START" x="0" y="0"/>
+ </objectgroup>
+ <objectgroup name="MAP_PORTAL_LAYER">
+  <object id="2" name="TOP_WORLD" x="0" y="0" width="16" height="16"/>
+  <object id="3" name="DUNGEON" x="0" y="0" width="16" height="16"/>
+  <object id="10" x="0" y="0" width="16" height="16"/>
+ </objectgroup>
+ <objectgroup name="MAP_ENEMY_SPAWN_LAYER">
+  <object id="4" name="7" x="0" y="0" width="16" height="16"/>
+  <object id="5" name="99" x="0" y="0" width="16" height="16"/>
+ </objectgroup>
+ <objectgroup name="MAP_QUEST_ITEM_SPAWN_LAYER">
+  <object id="6" name="1" x="0" y="0"><properties><property name="taskID" value="3"/></properties></object>
+  <object id="7" name="1" x="0" y="0"><properties><property name="taskID" value="8"/></properties></object>
+  <object id="8" name="4" x="0" y="0"><properties><property name="taskID" value="1"/></properties></object>
+  <object id="9" name="2" x="0" y="0"/>
+ </objectgroup>
+</map>"#;
+        let content = ::utils::tmx::parse_tmx(xml.as_bytes(), Path::new("."), &mut ::utils::tmx::TilesetCache::new()).unwrap();
+
+        let mut schema = MapSchema::standard();
+        schema.add_map("topworld");
+        schema.zones.insert("7".to_string());
+        schema.quests.insert("1".to_string(), ["1", "2", "3"].iter().map(|id| id.to_string()).collect());
+        schema.quests.insert("2".to_string(), ["1"].iter().map(|id| id.to_string()).collect());
+
+        let diagnostics = schema.check(&content);
+        assert_eq!(checks(&diagnostics), vec![
+            (SchemaCheck::RequiredLayer, "the map has no such object group"),
+            (SchemaCheck::MissingSpawn, "no object is named 'START'"),
+            (SchemaCheck::UnknownMap, "object 3 is named 'DUNGEON', which is not a known map"),
+            (SchemaCheck::ObjectSchema, "object 10 has no name"),
+            (SchemaCheck::UnknownZone, "object 5 is named '99', which is not a zone in monster_zones.json"),
+            (SchemaCheck::UnknownTask, "object 7 has taskID '8', which is not a task of quest '1'"),
+            (SchemaCheck::UnknownQuest, "object 8 is named '4', which is not a quest"),
+            (SchemaCheck::ObjectSchema, "object 9 has no taskID"),
+        ]);
+        assert_eq!(diagnostics[0].subject, "MAP_COLLISION_LAYER");
+        assert_eq!(diagnostics[3].severity, Severity::Warning);
+        assert!(diagnostics.iter().enumerate().all(|(idx, diagnostic)| idx == 3 || diagnostic.severity == Severity::Error));
+    }
+
+}
@@ -0,0 +1,200 @@
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+use super::noisy_float::prelude::*;
+
+/// A position or offset in world coordinates, i.e. pixels of the map with
+/// `y` growing downwards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Vector2 {
+    pub x: R32,
+    pub y: R32,
+}
+
+/// A position in world coordinates.
+pub type Point = Vector2;
+
+impl Vector2 {
+
+    pub fn new(x: R32, y: R32) -> Vector2 {
+        Vector2 { x, y }
+    }
+
+    pub fn zero() -> Vector2 {
+        Vector2::new(r32(0.0), r32(0.0))
+    }
+
+    /// The top left corner of the tile at `tile_x`/`tile_y`.
+    pub fn from_tile(tile_x: i32, tile_y: i32, tile_width: usize, tile_height: usize) -> Vector2 {
+        Vector2::new(r32(tile_x as f32 * tile_width as f32), r32(tile_y as f32 * tile_height as f32))
+    }
+
+    /// The tile this position lies in; positions left of or above the map
+    /// give negative tile coordinates.
+    pub fn to_tile(&self, tile_width: usize, tile_height: usize) -> (i32, i32) {
+        ((self.x.raw() / tile_width as f32).floor() as i32, (self.y.raw() / tile_height as f32).floor() as i32)
+    }
+
+    pub fn length(&self) -> R32 {
+        self.length_squared().sqrt()
+    }
+
+    pub fn length_squared(&self) -> R32 {
+        self.dot(self)
+    }
+
+    /// This vector scaled to a length of 1; the zero vector stays zero.
+    pub fn normalize(&self) -> Vector2 {
+        let length = self.length();
+        if length == r32(0.0) {
+            *self
+        } else {
+            *self / length
+        }
+    }
+
+    pub fn dot(&self, other: &Vector2) -> R32 {
+        self.x * other.x + self.y * other.y
+    }
+
+    pub fn distance_to(&self, other: &Vector2) -> R32 {
+        (*other - *self).length()
+    }
+
+    /// The point `t` of the way from this vector to `other`; `t` is not clamped.
+    pub fn lerp(&self, other: &Vector2, t: R32) -> Vector2 {
+        *self + (*other - *self) * t
+    }
+
+}
+
+impl Add for Vector2 {
+    type Output = Vector2;
+    fn add(self, other: Vector2) -> Vector2 {
+        Vector2::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl AddAssign for Vector2 {
+    fn add_assign(&mut self, other: Vector2) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Vector2 {
+    type Output = Vector2;
+    fn sub(self, other: Vector2) -> Vector2 {
+        Vector2::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl SubAssign for Vector2 {
+    fn sub_assign(&mut self, other: Vector2) {
+        *self = *self - other;
+    }
+}
+
+impl Neg for Vector2 {
+    type Output = Vector2;
+    fn neg(self) -> Vector2 {
+        Vector2::new(-self.x, -self.y)
+    }
+}
+
+impl Mul<R32> for Vector2 {
+    type Output = Vector2;
+    fn mul(self, factor: R32) -> Vector2 {
+        Vector2::new(self.x * factor, self.y * factor)
+    }
+}
+
+impl Div<R32> for Vector2 {
+    type Output = Vector2;
+    fn div(self, divisor: R32) -> Vector2 {
+        Vector2::new(self.x / divisor, self.y / divisor)
+    }
+}
+
+impl From<(R32, R32)> for Vector2 {
+    fn from((x, y): (R32, R32)) -> Vector2 {
+        Vector2::new(x, y)
+    }
+}
+
+impl From<Vector2> for (R32, R32) {
+    fn from(vector: Vector2) -> (R32, R32) {
+        (vector.x, vector.y)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use ::proptest::prelude::*;
+
+    fn v(x: f32, y: f32) -> Vector2 {
+        Vector2::new(r32(x), r32(y))
+    }
+
+    #[test]
+    fn vector_arithmetic() {
+        let mut a = v(3.0, 4.0);
+        assert_eq!(a + v(1.0, -2.0), v(4.0, 2.0));
+        assert_eq!(a - v(1.0, -2.0), v(2.0, 6.0));
+        assert_eq!(a * r32(2.0), v(6.0, 8.0));
+        assert_eq!(a / r32(2.0), v(1.5, 2.0));
+        assert_eq!(-a, v(-3.0, -4.0));
+
+        a += v(1.0, 1.0);
+        a -= v(2.0, 0.0);
+        assert_eq!(a, v(2.0, 5.0));
+    }
+
+    #[test]
+    fn vector_length() {
+        let a = v(3.0, 4.0);
+        assert_eq!(a.length(), r32(5.0));
+        assert_eq!(a.length_squared(), r32(25.0));
+        assert_eq!(a.normalize(), v(0.6, 0.8));
+        assert_eq!(Vector2::zero().normalize(), Vector2::zero());
+        assert_eq!(a.dot(&v(-4.0, 3.0)), r32(0.0));
+        assert_eq!(a.distance_to(&v(0.0, 0.0)), r32(5.0));
+    }
+
+    #[test]
+    fn vector_lerp() {
+        let a = v(0.0, 10.0);
+        let b = v(10.0, 20.0);
+        assert_eq!(a.lerp(&b, r32(0.0)), a);
+        assert_eq!(a.lerp(&b, r32(1.0)), b);
+        assert_eq!(a.lerp(&b, r32(0.25)), v(2.5, 12.5));
+    }
+
+    #[test]
+    fn vector_tiles() {
+        assert_eq!(Vector2::from_tile(3, -2, 16, 8), v(48.0, -16.0));
+        assert_eq!(v(47.9, 0.0).to_tile(16, 8), (2, 0));
+        assert_eq!(v(-0.5, -8.0).to_tile(16, 8), (-1, -1));
+    }
+
+    proptest! {
+
+        #[test]
+        fn tiles_round_trip(x in -100i32..100, y in -100i32..100, width in 1usize..64, height in 1usize..64) {
+            let corner = Vector2::from_tile(x, y, width, height);
+            prop_assert_eq!(corner.to_tile(width, height), (x, y));
+            let inside = corner + Vector2::new(r32(width as f32 - 0.5), r32(height as f32 - 0.5));
+            prop_assert_eq!(inside.to_tile(width, height), (x, y));
+        }
+
+        #[test]
+        fn normalized_vectors_have_unit_length(x in -1000i32..1000, y in -1000i32..1000) {
+            let vector = Vector2::new(r32(x as f32), r32(y as f32));
+            prop_assume!(vector != Vector2::zero());
+            prop_assert!((vector.normalize().length().raw() - 1.0).abs() < 1e-5);
+            prop_assert!(vector.normalize().dot(&vector) > r32(0.0));
+        }
+
+    }
+
+}